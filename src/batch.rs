@@ -0,0 +1,368 @@
+use serde::Serialize;
+use std::fmt;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Mutex;
+use std::thread;
+use std::time::Instant;
+
+use crate::bks::{self, BestKnownTable};
+use crate::bounds::Bounds;
+use crate::data::{Data, DistanceConvention};
+use crate::sol::Sol;
+use crate::{Conf, Ges, LogFormat};
+
+#[derive(Debug)]
+pub enum BatchError {
+    Io(std::io::Error),
+    Pattern(glob::PatternError),
+    NoInstances(String),
+}
+
+impl fmt::Display for BatchError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BatchError::Io(e) => write!(f, "cannot list instances: {e}"),
+            BatchError::Pattern(e) => write!(f, "invalid pattern: {e}"),
+            BatchError::NoInstances(pattern) => write!(f, "no instances match {pattern}"),
+        }
+    }
+}
+
+impl std::error::Error for BatchError {}
+
+impl From<std::io::Error> for BatchError {
+    fn from(e: std::io::Error) -> Self {
+        BatchError::Io(e)
+    }
+}
+
+impl From<glob::PatternError> for BatchError {
+    fn from(e: glob::PatternError) -> Self {
+        BatchError::Pattern(e)
+    }
+}
+
+/// How to solve every instance of a batch.
+#[derive(Clone)]
+pub struct BatchConf {
+    /// number of instances solved at the same time
+    pub jobs: usize,
+    /// number of runs of every instance, the seed of run `r` is `seed + r`
+    pub repetitions: usize,
+    pub convention: DistanceConvention,
+    /// longest time a route may take, replacing the limits of the instances
+    pub max_duration: Option<u64>,
+    /// settings of every run; a zero target is replaced by the best known number of routes
+    pub conf: Conf,
+    pub best_known: BestKnownTable,
+    /// numbers of routes to reach, taking precedence over the best-known ones
+    pub targets: BestKnownTable,
+}
+
+impl Default for BatchConf {
+    fn default() -> Self {
+        Self {
+            jobs: 1,
+            repetitions: 1,
+            convention: Default::default(),
+            max_duration: None,
+            conf: Default::default(),
+            best_known: BestKnownTable::builtin(),
+            targets: Default::default(),
+        }
+    }
+}
+
+impl BatchConf {
+    /// The target of `conf` unless 0, otherwise as `bks::target_routes` finds it.
+    pub fn target_routes(&self, instance: &str) -> Option<usize> {
+        let explicit = Some(self.conf.target_routes);
+
+        bks::target_routes(instance, explicit, &self.targets, &self.best_known)
+    }
+}
+
+/// Outcome of one run of an instance. Distances are in instance units and the time in seconds.
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct RunSummary {
+    pub instance: String,
+    pub repetition: usize,
+    pub seed: Option<u64>,
+    pub target_routes: Option<usize>,
+    pub routes: usize,
+    pub unserved: usize,
+    pub distance: f64,
+    pub time: f64,
+    pub best_routes: Option<usize>,
+    pub best_distance: Option<f64>,
+    pub route_gap: Option<i64>,
+    /// in percent of the best known distance
+    pub distance_gap: Option<f64>,
+    pub lower_bound: Option<usize>,
+    /// why the instance could not be solved, the other values are meaningless then
+    pub error: Option<String>,
+}
+
+impl RunSummary {
+    pub fn reached_target(&self) -> bool {
+        self.error.is_none()
+            && self.unserved == 0
+            && self
+                .target_routes
+                .is_some_and(|target| self.routes <= target)
+    }
+}
+
+/// Instance files of a directory, or the paths matching a glob pattern, in order.
+pub fn instances(pattern: &str) -> Result<Vec<PathBuf>, BatchError> {
+    let mut paths = if Path::new(pattern).is_dir() {
+        let mut paths = Vec::new();
+        for entry in std::fs::read_dir(pattern)? {
+            let path = entry?.path();
+            if path.is_file() {
+                paths.push(path);
+            }
+        }
+        paths
+    } else {
+        glob::glob(pattern)?
+            .filter_map(Result::ok)
+            .filter(|path| path.is_file())
+            .collect()
+    };
+
+    if paths.is_empty() {
+        return Err(BatchError::NoInstances(pattern.to_owned()));
+    }
+
+    paths.sort();
+    Ok(paths)
+}
+
+pub fn instance_name(path: &Path) -> String {
+    path.file_stem()
+        .unwrap_or_default()
+        .to_string_lossy()
+        .to_ascii_lowercase()
+}
+
+/// Runs every instance `batch.repetitions` times on `batch.jobs` threads and returns the
+/// summaries in the order of `paths`, then repetitions. `done` is called as soon as a run ends.
+pub fn run<F>(paths: &[PathBuf], batch: &BatchConf, done: F) -> Vec<RunSummary>
+where
+    F: Fn(&RunSummary) + Sync,
+{
+    let runs = paths.len() * batch.repetitions;
+    let next = AtomicUsize::new(0);
+    let summaries = Mutex::new(vec![None; runs]);
+
+    thread::scope(|s| {
+        for _ in 0..batch.jobs.max(1).min(runs) {
+            s.spawn(|| loop {
+                let i = next.fetch_add(1, Ordering::Relaxed);
+                if i >= runs {
+                    break;
+                }
+
+                let summary = solve(&paths[i / batch.repetitions], i % batch.repetitions, batch);
+                done(&summary);
+                summaries.lock().unwrap()[i] = Some(summary);
+            });
+        }
+    });
+
+    summaries
+        .into_inner()
+        .unwrap()
+        .into_iter()
+        .map(Option::unwrap)
+        .collect()
+}
+
+fn solve(path: &Path, repetition: usize, batch: &BatchConf) -> RunSummary {
+    let start = Instant::now();
+    let instance = instance_name(path);
+
+    let mut conf = batch.conf.clone();
+    conf.seed = conf.seed.map(|seed| seed.wrapping_add(repetition as u64));
+    conf.log_format = LogFormat::Silent;
+
+    let best = batch.best_known.get(&instance);
+    let target_routes = batch.target_routes(&instance);
+
+    let mut summary = RunSummary {
+        instance,
+        repetition,
+        seed: conf.seed,
+        target_routes,
+        routes: 0,
+        unserved: 0,
+        distance: 0.0,
+        time: 0.0,
+        best_routes: best.map(|b| b.routes),
+        best_distance: best.and_then(|b| b.distance),
+        route_gap: None,
+        distance_gap: None,
+        lower_bound: None,
+        error: None,
+    };
+
+    match Data::read_with(path, batch.convention) {
+        Ok(mut data) => {
+            if let Some(duration) = batch.max_duration {
+                data.set_max_duration(duration);
+            }
+            let lower_bound = Bounds::new(&data).routes();
+            summary.lower_bound = Some(lower_bound);
+            conf.target_routes = target_routes.unwrap_or(lower_bound);
+
+            let mut solution = Sol::new(&data);
+            solution.initialize();
+            Ges::new(&data).ges(&mut solution, conf);
+
+            summary.routes = solution.routes_number();
+            summary.unserved = solution.heap.size;
+            summary.distance = solution.cost().distance;
+
+            if let Some(best) = best {
+                let gap = best.gap(summary.routes, summary.distance);
+                summary.route_gap = Some(gap.routes);
+                summary.distance_gap = gap.distance;
+            }
+        }
+        Err(e) => summary.error = Some(e.to_string()),
+    }
+
+    summary.time = start.elapsed().as_secs_f64();
+    summary
+}
+
+pub fn write_csv<W: Write>(w: &mut W, summaries: &[RunSummary]) -> std::io::Result<()> {
+    writeln!(
+        w,
+        "instance,repetition,seed,target_routes,routes,unserved,distance,time,\
+         best_routes,best_distance,route_gap,distance_gap,lower_bound,error"
+    )?;
+
+    let optional = |x: Option<String>| x.unwrap_or_default();
+    for s in summaries {
+        writeln!(
+            w,
+            "{},{},{},{},{},{},{:.4},{:.3},{},{},{},{},{},{}",
+            s.instance,
+            s.repetition,
+            optional(s.seed.map(|x| x.to_string())),
+            optional(s.target_routes.map(|x| x.to_string())),
+            s.routes,
+            s.unserved,
+            s.distance,
+            s.time,
+            optional(s.best_routes.map(|x| x.to_string())),
+            optional(s.best_distance.map(|x| x.to_string())),
+            optional(s.route_gap.map(|x| x.to_string())),
+            optional(s.distance_gap.map(|x| format!("{x:.2}"))),
+            optional(s.lower_bound.map(|x| x.to_string())),
+            // error messages may contain commas and quotes
+            optional(
+                s.error
+                    .as_ref()
+                    .map(|e| format!("\"{}\"", e.replace('"', "\"\"")))
+            ),
+        )?;
+    }
+
+    Ok(())
+}
+
+pub fn write_json<W: Write>(w: &mut W, summaries: &[RunSummary]) -> std::io::Result<()> {
+    serde_json::to_writer_pretty(&mut *w, summaries)?;
+    writeln!(w)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::time::Duration;
+
+    fn instance_file(dir: &Path, name: &str, contents: &str) -> PathBuf {
+        let path = dir.join(name);
+        std::fs::write(&path, contents).unwrap();
+        path
+    }
+
+    #[test]
+    fn solves_every_instance() {
+        let dir = std::env::temp_dir().join(format!("ges-batch-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+
+        instance_file(
+            &dir,
+            "tiny.txt",
+            "2\t10\t1\n\
+             0\t0\t0\t0\t0\t100\t0\t0\t0\n\
+             1\t1\t0\t5\t0\t100\t1\t0\t2\n\
+             2\t2\t0\t-5\t0\t100\t1\t1\t0\n\
+             DISTANCE\n0 1 2\n1 0 1\n2 1 0\n",
+        );
+        instance_file(&dir, "broken.txt", "not an instance\n");
+
+        let paths = instances(dir.to_str().unwrap()).unwrap();
+        assert_eq!(paths.len(), 2);
+
+        let batch = BatchConf {
+            jobs: 2,
+            repetitions: 2,
+            conf: Conf {
+                max_optimization_time: Duration::new(5, 0),
+                seed: Some(1),
+                ..Default::default()
+            },
+            best_known: BestKnownTable::parse("tiny,1,5").unwrap(),
+            ..Default::default()
+        };
+        let summaries = run(&paths, &batch, |_| {});
+
+        let names = summaries
+            .iter()
+            .map(|s| s.instance.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(names, ["broken", "broken", "tiny", "tiny"]);
+        assert!(summaries[0].error.is_some());
+
+        let tiny = &summaries[3];
+        assert_eq!(tiny.repetition, 1);
+        assert_eq!(tiny.seed, Some(2));
+        assert_eq!(tiny.routes, 1);
+        assert_eq!(tiny.unserved, 0);
+        assert_eq!(tiny.distance, 4.0);
+        assert_eq!(tiny.target_routes, Some(1));
+        assert_eq!(tiny.route_gap, Some(0));
+        assert_eq!(tiny.distance_gap, Some(-20.0));
+        assert!(tiny.reached_target());
+        assert_eq!(tiny.lower_bound, Some(1));
+
+        let mut csv = Vec::new();
+        write_csv(&mut csv, &summaries).unwrap();
+        assert_eq!(String::from_utf8(csv).unwrap().lines().count(), 5);
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn targets() {
+        let mut batch = BatchConf {
+            targets: BestKnownTable::parse("lc101,12\nmine,3").unwrap(),
+            ..Default::default()
+        };
+
+        assert_eq!(batch.target_routes("lc101"), Some(12));
+        assert_eq!(batch.target_routes("lc102"), Some(10));
+        assert_eq!(batch.target_routes("mine"), Some(3));
+        assert_eq!(batch.target_routes("unknown"), None);
+
+        batch.conf.target_routes = 7;
+        assert_eq!(batch.target_routes("lc101"), Some(7));
+    }
+}
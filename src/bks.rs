@@ -0,0 +1,259 @@
+//! Best-known solutions of benchmark instances. Besides the number of routes kept in
+//! `routes::ROUTES`, a table can be read from CSV lines of the form
+//!
+//! ```text
+//! instance,routes,distance,reference
+//! lc101,10,828.94,Li & Lim (2001)
+//! ```
+//!
+//! where the distance and the reference may be left empty. A header line and lines starting
+//! with `#` are skipped. The built-in table has no distances, so gaps in distance are only
+//! reported for instances of such a file.
+
+use serde::Serialize;
+use std::collections::HashMap;
+use std::fmt;
+use std::io;
+use std::path::Path;
+
+use crate::routes::ROUTES;
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct BestKnown {
+    pub routes: usize,
+    pub distance: Option<f64>,
+    pub reference: Option<String>,
+}
+
+/// How much worse a result is than the best known one: the difference in routes, and the
+/// difference in distance relative to the best known distance, in percent.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub struct Gap {
+    pub routes: i64,
+    pub distance: Option<f64>,
+}
+
+impl BestKnown {
+    pub fn gap(&self, routes: usize, distance: f64) -> Gap {
+        Gap {
+            routes: routes as i64 - self.routes as i64,
+            distance: self
+                .distance
+                .filter(|&best| best > 0.0)
+                .map(|best| (distance - best) / best * 100.0),
+        }
+    }
+}
+
+impl fmt::Display for Gap {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "routes {:+}", self.routes)?;
+        if let Some(distance) = self.distance {
+            write!(f, ", distance {distance:+.2}%")?;
+        }
+
+        Ok(())
+    }
+}
+
+#[derive(Debug)]
+pub enum BksError {
+    Io(io::Error),
+    Parse { line: usize, msg: String },
+}
+
+impl fmt::Display for BksError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BksError::Io(e) => write!(f, "cannot read best-known solutions: {e}"),
+            BksError::Parse { line, msg } => write!(f, "line {line}: {msg}"),
+        }
+    }
+}
+
+impl std::error::Error for BksError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            BksError::Io(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for BksError {
+    fn from(e: io::Error) -> Self {
+        BksError::Io(e)
+    }
+}
+
+/// Best-known solutions by lowercase instance name.
+#[derive(Debug, Clone, Default)]
+pub struct BestKnownTable {
+    entries: HashMap<String, BestKnown>,
+}
+
+impl BestKnownTable {
+    /// Route counts of the Li & Lim instances. Best-known distances are not built in, they have
+    /// to come from a file.
+    pub fn builtin() -> Self {
+        let entries = ROUTES
+            .entries()
+            .map(|(&instance, &routes)| {
+                let best = BestKnown {
+                    routes,
+                    distance: None,
+                    reference: None,
+                };
+                (instance.to_owned(), best)
+            })
+            .collect();
+
+        Self { entries }
+    }
+
+    pub fn read(path: &Path) -> Result<Self, BksError> {
+        Self::parse(&std::fs::read_to_string(path)?)
+    }
+
+    pub fn parse(contents: &str) -> Result<Self, BksError> {
+        let mut entries = HashMap::new();
+        let mut first = true;
+
+        for (i, line) in contents.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            let mut fields = line.splitn(4, ',').map(str::trim);
+            let instance = fields.next().unwrap_or_default();
+            let routes = fields.next().unwrap_or_default();
+            let header = first && instance == "instance";
+            first = false;
+            if header {
+                continue;
+            }
+
+            let parse_error = |msg: String| BksError::Parse { line: i + 1, msg };
+            let routes = routes
+                .parse()
+                .map_err(|_| parse_error(format!("invalid number of routes {routes:?}")))?;
+            let distance = match fields.next().filter(|d| !d.is_empty()) {
+                Some(d) => Some(
+                    d.parse()
+                        .map_err(|_| parse_error(format!("invalid distance {d:?}")))?,
+                ),
+                None => None,
+            };
+            let reference = fields.next().filter(|r| !r.is_empty()).map(str::to_owned);
+
+            let best = BestKnown {
+                routes,
+                distance,
+                reference,
+            };
+            entries.insert(instance.to_ascii_lowercase(), best);
+        }
+
+        Ok(Self { entries })
+    }
+
+    /// Adds the entries of the other table, replacing those already present.
+    pub fn extend(&mut self, other: BestKnownTable) {
+        self.entries.extend(other.entries);
+    }
+
+    pub fn get(&self, instance: &str) -> Option<&BestKnown> {
+        self.entries.get(&instance.to_ascii_lowercase())
+    }
+}
+
+/// Number of routes a search of the instance aims at: `explicit` unless 0, otherwise the one in
+/// `targets`, otherwise the best known one. Without any, the search aims at the lower bound of
+/// the instance.
+pub fn target_routes(
+    instance: &str,
+    explicit: Option<usize>,
+    targets: &BestKnownTable,
+    best_known: &BestKnownTable,
+) -> Option<usize> {
+    explicit.filter(|&routes| routes > 0).or_else(|| {
+        targets
+            .get(instance)
+            .or_else(|| best_known.get(instance))
+            .map(|best| best.routes)
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn parse() {
+        let table = BestKnownTable::parse(
+            "instance,routes,distance,reference\n\
+             # comment\n\
+             LC101,10,828.94,Li, Lim\n\
+             \n\
+             mine,3,,\n",
+        )
+        .unwrap();
+
+        let lc101 = table.get("lc101").unwrap();
+        assert_eq!(lc101.routes, 10);
+        assert_eq!(lc101.distance, Some(828.94));
+        assert_eq!(lc101.reference.as_deref(), Some("Li, Lim"));
+
+        let mine = table.get("mine").unwrap();
+        assert_eq!(mine.distance, None);
+        assert_eq!(mine.reference, None);
+
+        let commented = BestKnownTable::parse("# by hand\n\ninstance,routes\nlc101,9\n").unwrap();
+        assert_eq!(commented.get("lc101").unwrap().routes, 9);
+        assert!(matches!(
+            BestKnownTable::parse("lc101,9\ninstance,routes\n"),
+            Err(BksError::Parse { line: 2, .. })
+        ));
+
+        assert!(matches!(
+            BestKnownTable::parse("lc101,ten\n"),
+            Err(BksError::Parse { line: 1, .. })
+        ));
+        assert!(matches!(
+            BestKnownTable::parse("lc101,10,far\n"),
+            Err(BksError::Parse { line: 1, .. })
+        ));
+    }
+
+    #[test]
+    fn extend_and_gap() {
+        let mut table = BestKnownTable::builtin();
+        assert_eq!(table.get("lc103").unwrap().routes, 9);
+        assert_eq!(table.get("lc101").unwrap().distance, None);
+
+        table.extend(BestKnownTable::parse("lc101,10,800").unwrap());
+        let lc101 = table.get("LC101").unwrap();
+
+        let gap = lc101.gap(11, 820.0);
+        assert_eq!(gap.routes, 1);
+        assert!((gap.distance.unwrap() - 2.5).abs() < 1e-9);
+        assert_eq!(gap.to_string(), "routes +1, distance +2.50%");
+
+        let gap = table.get("lc103").unwrap().gap(9, 1000.0);
+        assert_eq!(gap.to_string(), "routes +0");
+    }
+
+    #[test]
+    fn target_routes() {
+        let targets = BestKnownTable::parse("mine,3").unwrap();
+        let best_known = BestKnownTable::builtin();
+        let target =
+            |instance, explicit| super::target_routes(instance, explicit, &targets, &best_known);
+
+        assert_eq!(target("mine", Some(5)), Some(5));
+        assert_eq!(target("mine", Some(0)), Some(3));
+        assert_eq!(target("lc103", None), Some(9));
+        assert_eq!(target("unknown", Some(0)), None);
+    }
+}
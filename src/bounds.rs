@@ -0,0 +1,210 @@
+//! Lower bounds on the number of vehicles needed to serve an instance.
+
+use std::fmt;
+
+use itertools::Itertools;
+
+use crate::data::Data;
+use crate::eval::Eval;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Bounds {
+    pub capacity: usize,
+    pub time_windows: usize,
+}
+
+impl Bounds {
+    pub fn new(data: &Data) -> Self {
+        Self {
+            capacity: capacity_bound(data),
+            time_windows: incompatibility_bound(data),
+        }
+    }
+
+    /// The best of the bounds; any solution has at least one route.
+    pub fn routes(&self) -> usize {
+        self.capacity.max(self.time_windows).max(1)
+    }
+}
+
+impl fmt::Display for Bounds {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "lower bound: {} (capacity {}, time windows {})",
+            self.routes(),
+            self.capacity,
+            self.time_windows
+        )
+    }
+}
+
+fn pickups(data: &Data) -> Vec<usize> {
+    (1..data.points)
+        .filter(|&i| !data.pts[i].is_delivery)
+        .collect()
+}
+
+/// Vehicles unload on the way, so the total demand divided by the capacity bounds nothing.
+/// Instead, a request is surely on board of some vehicle between the latest start of service at
+/// its pickup and the earliest one at its delivery, and all vehicles together must be able to
+/// carry the load that is surely on board at the same time.
+pub fn capacity_bound(data: &Data) -> usize {
+    if data.max_cap <= 0 {
+        return 0;
+    }
+
+    // (time, change in load), unloading before loading when at the same time
+    let events = pickups(data)
+        .into_iter()
+        .filter_map(|pickup| {
+            let delivery = data.pair_of(pickup);
+            let (loaded, unloaded) = (data.pts[pickup].due, data.pts[delivery].start);
+            let dem = data.pts[pickup].dem as i64;
+            (loaded < unloaded).then_some([(loaded, dem), (unloaded, -dem)])
+        })
+        .flatten()
+        .sorted_by_key(|&(time, change)| (time, change));
+
+    let mut load = 0;
+    let mut max_load = 0;
+    for (_, change) in events {
+        load += change;
+        max_load = max_load.max(load);
+    }
+
+    let cap = data.max_cap as i64;
+    ((max_load + cap - 1) / cap) as usize
+}
+
+/// Whether both requests fit into one route, trying every order of their nodes.
+fn compatible(data: &Data, a: usize, b: usize) -> bool {
+    let (pa, da) = (a, data.pair_of(a));
+    let (pb, db) = (b, data.pair_of(b));
+    let orders = [
+        [pa, da, pb, db],
+        [pb, db, pa, da],
+        [pa, pb, da, db],
+        [pa, pb, db, da],
+        [pb, pa, da, db],
+        [pb, pa, db, da],
+    ];
+
+    orders.iter().any(|order| {
+        let mut eval = Eval::new();
+        order.iter().chain([0].iter()).all(|&node| {
+            eval.next(node, data);
+            eval.is_feasible(data)
+        })
+    })
+}
+
+/// Size of a set of requests no two of which fit into one route, so that each needs its own
+/// vehicle. Finding the largest such set is NP-hard; this grows one greedily from every request
+/// and keeps the largest.
+pub fn incompatibility_bound(data: &Data) -> usize {
+    let pickups = pickups(data);
+    let n = pickups.len();
+
+    let mut incompatible = vec![vec![false; n]; n];
+    for i in 0..n {
+        for j in i + 1..n {
+            let x = !compatible(data, pickups[i], pickups[j]);
+            incompatible[i][j] = x;
+            incompatible[j][i] = x;
+        }
+    }
+
+    let degree = incompatible
+        .iter()
+        .map(|row| row.iter().filter(|&&x| x).count())
+        .collect_vec();
+    let by_degree = (0..n)
+        .sorted_by_key(|&i| std::cmp::Reverse(degree[i]))
+        .collect_vec();
+
+    let mut best = n.min(1);
+    for &start in by_degree.iter() {
+        if degree[start] < best {
+            // no larger set can contain it
+            break;
+        }
+
+        let mut clique = vec![start];
+        for &candidate in by_degree.iter() {
+            if clique.iter().all(|&member| incompatible[member][candidate]) {
+                clique.push(candidate);
+            }
+        }
+
+        best = best.max(clique.len());
+    }
+
+    best
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::data::builder::{DataBuilder, Node};
+
+    fn node(x: f64, dem: i32, start: u64, due: u64) -> Node {
+        Node {
+            x,
+            dem,
+            start,
+            due,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn capacity() {
+        // both requests are on board during [10, 20), together they need two vehicles
+        let mut builder = DataBuilder::new(10);
+        builder.depot(node(0.0, 0, 0, 100));
+        builder.request(node(1.0, 6, 0, 10), node(1.0, -6, 20, 100));
+        builder.request(node(2.0, 6, 0, 10), node(2.0, -6, 20, 100));
+        // no deadline at the pickup, so never surely on board
+        builder.request(node(3.0, 10, 0, 100), node(3.0, -10, 0, 100));
+        let data = builder.build().unwrap();
+
+        assert_eq!(capacity_bound(&data), 2);
+        // one after the other they miss the pickup windows, at once they overload the vehicle
+        assert_eq!(incompatibility_bound(&data), 2);
+        assert_eq!(Bounds::new(&data).routes(), 2);
+    }
+
+    #[test]
+    fn time_windows() {
+        // three requests that must be served at the same time far apart from each other
+        let mut builder = DataBuilder::new(100);
+        builder.depot(node(0.0, 0, 0, 1000));
+        for x in [100.0, 200.0, 300.0] {
+            builder.request(node(x, 1, 150, 160), node(x, -1, 150, 170));
+        }
+        builder.request(node(1.0, 1, 0, 1000), node(1.0, -1, 0, 1000));
+        let data = builder.build().unwrap();
+
+        assert_eq!(incompatibility_bound(&data), 3);
+        assert_eq!(capacity_bound(&data), 0);
+        assert_eq!(
+            Bounds::new(&data).to_string(),
+            "lower bound: 3 (capacity 0, time windows 3)"
+        );
+    }
+
+    #[test]
+    fn max_duration() {
+        // together 40 long, waiting for the depot to open does not count
+        let mut builder = DataBuilder::new(100);
+        builder.depot(node(0.0, 0, 100, 1000));
+        for x in [10.0, -10.0] {
+            builder.request(node(x, 1, 0, 1000), node(x, -1, 0, 1000));
+        }
+        builder.max_duration(45);
+        let data = builder.build().unwrap();
+
+        assert_eq!(incompatibility_bound(&data), 1);
+    }
+}
@@ -0,0 +1,42 @@
+//! Checkpoints of a running search, from which `Ges::resume` continues it as if it had never
+//! stopped.
+
+use serde::{Deserialize, Serialize};
+use std::io;
+use std::path::Path;
+
+use crate::sol::snapshot::{SnapshotError, SolSnapshot};
+use crate::stats::Counters;
+use crate::Prng;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Checkpoint {
+    /// the solution being searched, with the pairs still ejected by the current route removal
+    pub solution: SolSnapshot,
+    /// the last solution serving every pair
+    pub complete: SolSnapshot,
+    pub solution_rng: Prng,
+    pub evaluator_rng: Prng,
+    pub counters: Counters,
+}
+
+impl Checkpoint {
+    pub fn read(path: &Path) -> Result<Self, SnapshotError> {
+        let file = io::BufReader::new(std::fs::File::open(path)?);
+        Ok(serde_json::from_reader(file)?)
+    }
+
+    /// Writes next to the path first, so that a crash while writing keeps the previous checkpoint.
+    pub fn write(&self, path: &Path) -> Result<(), SnapshotError> {
+        let mut partial = path.as_os_str().to_owned();
+        partial.push(".partial");
+
+        let mut file = io::BufWriter::new(std::fs::File::create(&partial)?);
+        serde_json::to_writer(&mut file, self)?;
+        io::Write::flush(&mut file)?;
+        drop(file);
+
+        std::fs::rename(&partial, path)?;
+        Ok(())
+    }
+}
@@ -1,10 +1,60 @@
+use std::fmt;
 use std::path::Path;
+use std::str::FromStr;
 use verifier::{read, verify::instance::Instance};
 
-pub const PTS: usize = 2000;
-pub const SIZE: usize = PTS * PTS;
+use self::builder::{DataBuilder, Node};
+
+pub mod builder;
+pub mod extended;
+
 pub const MULT: u64 = 10000;
 
+/// How distances (and travel times) in instance units are turned into the integer values kept in
+/// `Data`, which are always expressed in `1 / MULT` instance units.
+#[derive(Default, Copy, Clone, Debug, PartialEq, Eq)]
+pub enum DistanceConvention {
+    /// rounded up with `MULT` precision
+    #[default]
+    Ceil,
+    /// truncated to one decimal, as in the Solomon literature
+    OneDecimal,
+    /// rounded to the nearest integer, as in CVRPLIB
+    Integer,
+    /// rounded to the nearest value with `MULT` precision
+    Exact,
+}
+
+impl DistanceConvention {
+    pub fn scale(self, x: f64) -> u64 {
+        let scaled = match self {
+            DistanceConvention::Ceil => (x * MULT as f64).ceil(),
+            // the epsilon keeps values like 2.3 from being truncated to 2.2
+            DistanceConvention::OneDecimal => (x * 10.0 + 1e-9).trunc() * (MULT / 10) as f64,
+            DistanceConvention::Integer => x.round() * MULT as f64,
+            DistanceConvention::Exact => (x * MULT as f64).round(),
+        };
+
+        scaled as u64
+    }
+}
+
+impl FromStr for DistanceConvention {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "ceil" => Ok(DistanceConvention::Ceil),
+            "one-decimal" => Ok(DistanceConvention::OneDecimal),
+            "integer" => Ok(DistanceConvention::Integer),
+            "exact" => Ok(DistanceConvention::Exact),
+            _ => Err(format!(
+                "unknown distance convention {s:?}, expected one of: ceil, one-decimal, integer, exact"
+            )),
+        }
+    }
+}
+
 #[derive(Default, Copy, Clone, Debug)]
 pub struct PointData {
     pub dem: i32,
@@ -17,66 +67,283 @@ pub struct PointData {
 #[derive(Debug)]
 pub struct Data {
     pub dist: Vec<u64>,
-    pub pts: [PointData; PTS],
+    pub pts: Vec<PointData>,
     pub max_cap: i32,
     pub time: Vec<u64>,
     pub points: usize,
+    /// longest time a route may take from leaving the depot until it is back, `u64::MAX` for
+    /// no limit
+    pub max_duration: u64,
 }
 
-pub fn idx(a: usize, b: usize) -> usize {
-    a * PTS + b
+#[derive(Debug)]
+pub enum DataError {
+    Io(std::io::Error),
+    Parse(String),
+    DepotMissing,
+    IdOutOfRange { id: usize, points: usize },
+    DuplicateId(usize),
+    MissingNode(usize),
+    UnpairedNode(usize),
+    AsymmetricPairing { node: usize, pair: usize },
+    DemandSignMismatch { pickup: usize, delivery: usize },
+    MatrixSize { expected: usize, actual: usize },
+}
+
+impl fmt::Display for DataError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DataError::Io(e) => write!(f, "cannot read instance: {e}"),
+            DataError::Parse(e) => write!(f, "cannot parse instance: {e}"),
+            DataError::DepotMissing => write!(f, "depot (node 0) is missing"),
+            DataError::IdOutOfRange { id, points } => {
+                write!(f, "node {id} is out of range for {points} points")
+            }
+            DataError::DuplicateId(id) => write!(f, "node {id} is defined more than once"),
+            DataError::MissingNode(id) => write!(f, "node {id} is not defined"),
+            DataError::UnpairedNode(id) => write!(f, "node {id} has no pickup/delivery pair"),
+            DataError::AsymmetricPairing { node, pair } => {
+                write!(
+                    f,
+                    "node {node} is paired with {pair}, but not the other way round"
+                )
+            }
+            DataError::DemandSignMismatch { pickup, delivery } => write!(
+                f,
+                "pickup {pickup} must have non-negative and delivery {delivery} non-positive demand"
+            ),
+            DataError::MatrixSize { expected, actual } => {
+                write!(f, "matrix has {actual} entries instead of {expected}")
+            }
+        }
+    }
+}
+
+impl std::error::Error for DataError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            DataError::Io(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<std::io::Error> for DataError {
+    fn from(e: std::io::Error) -> Self {
+        DataError::Io(e)
+    }
 }
 
 impl Data {
-    pub fn read(path: &Path) -> Self {
-        let instance = read::<Instance>(path).unwrap();
+    pub fn read(path: &Path) -> Result<Self, DataError> {
+        Self::read_with(path, Default::default())
+    }
+
+    pub fn read_with(path: &Path, convention: DistanceConvention) -> Result<Self, DataError> {
+        let contents = std::fs::read_to_string(path)?;
+        let mut builder = if extended::has_matrices(&contents) {
+            extended::parse(&contents)?
+        } else {
+            let mut builder = Self::read_li_lim(path)?;
+            if let Some(max_duration) = extended::max_duration(&contents)? {
+                builder.max_duration(max_duration);
+            }
+            builder
+        };
+
+        builder.convention(convention).build()
+    }
 
-        let mut dist = vec![u64::MAX; SIZE];
-        let mut time = vec![u64::MAX; SIZE];
-        let mut pts = [Default::default(); PTS];
+    fn read_li_lim(path: &Path) -> Result<DataBuilder, DataError> {
+        let instance = read::<Instance>(path).map_err(|e| DataError::Parse(format!("{e:?}")))?;
+
+        let points = instance.pts.len();
+        let mut builder = DataBuilder::new(instance.max_capacity);
 
         for a in instance.pts.iter() {
-            let (p, d) = a.pickup_delivery.unwrap();
+            let id = a.id as usize;
+            if id >= points {
+                return Err(DataError::IdOutOfRange { id, points });
+            }
+
+            let (p, d) = a.pickup_delivery.ok_or(DataError::UnpairedNode(id))?;
             let pair = if p == 0 { d } else { p };
 
-            pts[a.id as usize] = PointData {
+            let node = Node {
+                x: a.x as f64,
+                y: a.y as f64,
                 dem: a.demand,
-                start: a.start as u64 * MULT,
-                due: a.due as u64 * MULT,
-                pair: pair as usize,
-                is_delivery: d == 0,
+                start: a.start as u64,
+                due: a.due as u64,
+                service: a.service as u64,
             };
 
-            for b in instance.pts.iter() {
-                let xs = a.x - b.x;
-                let xs2 = (xs * xs) as u64;
+            builder.point(id, node, pair as usize, d == 0)?;
+        }
 
-                let ys = a.y - b.y;
-                let ys2 = (ys * ys) as u64;
+        Ok(builder)
+    }
 
-                let shifted = xs2 + ys2;
-                let sqrted = ((shifted as f64).sqrt() * MULT as f64).ceil() as u64;
+    pub fn idx(&self, a: usize, b: usize) -> usize {
+        a * self.points + b
+    }
 
-                let loc = idx(a.id as usize, b.id as usize);
-                dist[loc] = sqrted;
-                time[loc] = sqrted + a.service as u64 * MULT;
-            }
+    pub fn pair_of(&self, idx: usize) -> usize {
+        self.pts[idx].pair
+    }
+
+    pub fn time_between(&self, from: usize, to: usize) -> u64 {
+        self.time[self.idx(from, to)]
+    }
+
+    pub fn dist_between(&self, from: usize, to: usize) -> u64 {
+        self.dist[self.idx(from, to)]
+    }
+
+    /// Limits the duration of every route, given in instance units.
+    pub fn set_max_duration(&mut self, duration: u64) {
+        self.max_duration = duration.saturating_mul(MULT);
+    }
+
+    pub fn limits_duration(&self) -> bool {
+        self.max_duration != u64::MAX
+    }
+}
+
+fn check_pairing(pts: &[PointData]) -> Result<(), DataError> {
+    let points = pts.len();
+
+    for (node, pt) in pts.iter().enumerate().skip(1) {
+        let pair = pt.pair;
+        if pair == 0 || pair == node {
+            return Err(DataError::UnpairedNode(node));
+        }
+        if pair >= points {
+            return Err(DataError::IdOutOfRange { id: pair, points });
         }
 
-        Data {
-            dist,
-            time,
-            max_cap: instance.max_capacity,
-            pts,
-            points: instance.pts.len(),
+        let other = &pts[pair];
+        if other.pair != node || other.is_delivery == pt.is_delivery {
+            return Err(DataError::AsymmetricPairing { node, pair });
+        }
+
+        let (pickup, delivery) = if pt.is_delivery {
+            (pair, node)
+        } else {
+            (node, pair)
+        };
+        if pts[pickup].dem < 0 || pts[delivery].dem > 0 {
+            return Err(DataError::DemandSignMismatch { pickup, delivery });
         }
     }
 
-    pub fn pair_of(&self, idx: usize) -> usize {
-        self.pts[idx].pair
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn pt(dem: i32, pair: usize, is_delivery: bool) -> PointData {
+        PointData {
+            dem,
+            pair,
+            is_delivery,
+            ..Default::default()
+        }
     }
 
-    pub fn time_between(&self, from: usize, to: usize) -> u64 {
-        self.time[idx(from, to)]
+    fn depot() -> PointData {
+        pt(0, 0, true)
+    }
+
+    #[test]
+    fn correct_pairing() {
+        let pts = vec![depot(), pt(10, 2, false), pt(-10, 1, true)];
+        assert!(check_pairing(&pts).is_ok());
+    }
+
+    #[test]
+    fn unpaired() {
+        let pts = vec![depot(), pt(10, 0, false), pt(-10, 1, true)];
+        assert!(matches!(
+            check_pairing(&pts),
+            Err(DataError::UnpairedNode(1))
+        ));
+    }
+
+    #[test]
+    fn pair_out_of_range() {
+        let pts = vec![depot(), pt(10, 5, false), pt(-10, 1, true)];
+        assert!(matches!(
+            check_pairing(&pts),
+            Err(DataError::IdOutOfRange { id: 5, points: 3 })
+        ));
+    }
+
+    #[test]
+    fn asymmetric() {
+        let pts = vec![
+            depot(),
+            pt(10, 2, false),
+            pt(-10, 3, true),
+            pt(10, 2, false),
+        ];
+        assert!(matches!(
+            check_pairing(&pts),
+            Err(DataError::AsymmetricPairing { node: 1, pair: 2 })
+        ));
+
+        let pts = vec![depot(), pt(10, 2, false), pt(10, 1, false)];
+        assert!(matches!(
+            check_pairing(&pts),
+            Err(DataError::AsymmetricPairing { node: 1, pair: 2 })
+        ));
+    }
+
+    #[test]
+    fn demand_sign() {
+        let pts = vec![depot(), pt(-10, 2, false), pt(10, 1, true)];
+        assert!(matches!(
+            check_pairing(&pts),
+            Err(DataError::DemandSignMismatch {
+                pickup: 1,
+                delivery: 2
+            })
+        ));
+    }
+
+    #[test]
+    fn conventions() {
+        let d = 2.0f64.sqrt() * 10.0;
+
+        assert_eq!(DistanceConvention::Ceil.scale(d), 141422);
+        assert_eq!(DistanceConvention::OneDecimal.scale(d), 141000);
+        assert_eq!(DistanceConvention::OneDecimal.scale(2.3), 23000);
+        assert_eq!(DistanceConvention::Integer.scale(d), 140000);
+        assert_eq!(DistanceConvention::Exact.scale(d), 141421);
+
+        assert_eq!("one-decimal".parse(), Ok(DistanceConvention::OneDecimal));
+        assert!("nearest".parse::<DistanceConvention>().is_err());
+    }
+
+    #[test]
+    fn max_duration_without_matrices() {
+        let path = std::env::temp_dir().join(format!("ges-max-duration-{}", std::process::id()));
+        let instance =
+            "2 10 1 480\n0 0 0 0 0 100 0 0 0\n1 0 0 5 0 100 2 0 2\n2 0 0 -5 0 100 0 1 0\n";
+        std::fs::write(&path, instance).unwrap();
+        let data = Data::read(&path);
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!(data.unwrap().max_duration, 480 * MULT);
+    }
+
+    #[test]
+    fn missing_file() {
+        assert!(matches!(
+            Data::read(Path::new("/nonexistent/instance.txt")),
+            Err(DataError::Io(_))
+        ));
     }
 }
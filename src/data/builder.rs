@@ -0,0 +1,288 @@
+use super::{check_pairing, Data, DataError, DistanceConvention, PointData, MULT};
+
+#[derive(Default, Copy, Clone, Debug)]
+pub struct Node {
+    pub x: f64,
+    pub y: f64,
+    pub dem: i32,
+    pub start: u64,
+    pub due: u64,
+    pub service: u64,
+}
+
+#[derive(Copy, Clone, Debug)]
+struct Point {
+    node: Node,
+    pair: usize,
+    is_delivery: bool,
+}
+
+/// Builds `Data` from in-memory nodes. Coordinates, times and matrix entries are given in
+/// instance units; times are scaled by `MULT`, distances and travel times according to the
+/// `DistanceConvention`. Without an explicit
+/// distance matrix, distances are euclidean; without an explicit travel time matrix, travel
+/// times are equal to distances. The service time of the node the vehicle leaves is added to
+/// every travel time.
+pub struct DataBuilder {
+    max_cap: i32,
+    points: Vec<Option<Point>>,
+    dist: Option<Vec<f64>>,
+    time: Option<Vec<f64>>,
+    convention: DistanceConvention,
+    max_duration: Option<u64>,
+}
+
+impl DataBuilder {
+    pub fn new(max_cap: i32) -> Self {
+        Self {
+            max_cap,
+            points: vec![None],
+            dist: None,
+            time: None,
+            convention: Default::default(),
+            max_duration: None,
+        }
+    }
+
+    pub fn depot(&mut self, depot: Node) -> &mut Self {
+        self.points[0] = Some(Point {
+            node: depot,
+            pair: 0,
+            is_delivery: true,
+        });
+        self
+    }
+
+    /// Appends a pickup and its delivery, returning their ids.
+    pub fn request(&mut self, pickup: Node, delivery: Node) -> (usize, usize) {
+        let pickup_id = self.points.len();
+        let delivery_id = pickup_id + 1;
+
+        self.points.push(Some(Point {
+            node: pickup,
+            pair: delivery_id,
+            is_delivery: false,
+        }));
+        self.points.push(Some(Point {
+            node: delivery,
+            pair: pickup_id,
+            is_delivery: true,
+        }));
+
+        (pickup_id, delivery_id)
+    }
+
+    /// Places a node under an explicit id, for sources which number the nodes themselves.
+    pub fn point(
+        &mut self,
+        id: usize,
+        node: Node,
+        pair: usize,
+        is_delivery: bool,
+    ) -> Result<&mut Self, DataError> {
+        if id >= self.points.len() {
+            self.points.resize(id + 1, None);
+        }
+
+        if self.points[id].is_some() {
+            return Err(DataError::DuplicateId(id));
+        }
+
+        self.points[id] = Some(Point {
+            node,
+            pair,
+            is_delivery,
+        });
+
+        Ok(self)
+    }
+
+    /// Row-major `points × points` distance matrix.
+    pub fn distances(&mut self, matrix: Vec<f64>) -> &mut Self {
+        self.dist = Some(matrix);
+        self
+    }
+
+    /// Row-major `points × points` travel time matrix, not including service times.
+    pub fn travel_times(&mut self, matrix: Vec<f64>) -> &mut Self {
+        self.time = Some(matrix);
+        self
+    }
+
+    pub fn convention(&mut self, convention: DistanceConvention) -> &mut Self {
+        self.convention = convention;
+        self
+    }
+
+    /// Longest time a route may take in instance units, from leaving the depot as late as its
+    /// time windows allow until it is back.
+    pub fn max_duration(&mut self, duration: u64) -> &mut Self {
+        self.max_duration = Some(duration);
+        self
+    }
+
+    pub fn build(&self) -> Result<Data, DataError> {
+        let points = self.points.len();
+
+        let mut nodes = Vec::with_capacity(points);
+        for (id, point) in self.points.iter().enumerate() {
+            match point {
+                Some(p) => nodes.push(*p),
+                None if id == 0 => return Err(DataError::DepotMissing),
+                None => return Err(DataError::MissingNode(id)),
+            }
+        }
+
+        let pts = nodes
+            .iter()
+            .map(|p| PointData {
+                dem: p.node.dem,
+                start: p.node.start * MULT,
+                due: p.node.due * MULT,
+                pair: p.pair,
+                is_delivery: p.is_delivery,
+            })
+            .collect::<Vec<_>>();
+
+        check_pairing(&pts)?;
+
+        for matrix in [&self.dist, &self.time].into_iter().flatten() {
+            if matrix.len() != points * points {
+                return Err(DataError::MatrixSize {
+                    expected: points * points,
+                    actual: matrix.len(),
+                });
+            }
+        }
+
+        let mut dist = vec![u64::MAX; points * points];
+        let mut time = vec![u64::MAX; points * points];
+
+        for (a, from) in nodes.iter().enumerate() {
+            for (b, to) in nodes.iter().enumerate() {
+                let loc = a * points + b;
+
+                let d = match &self.dist {
+                    Some(matrix) => matrix[loc],
+                    None => {
+                        let xs = from.node.x - to.node.x;
+                        let ys = from.node.y - to.node.y;
+                        (xs * xs + ys * ys).sqrt()
+                    }
+                };
+                let scaled = self.convention.scale(d);
+
+                let travel = match &self.time {
+                    Some(matrix) => self.convention.scale(matrix[loc]),
+                    None => scaled,
+                };
+
+                dist[loc] = scaled;
+                time[loc] = travel + from.node.service * MULT;
+            }
+        }
+
+        Ok(Data {
+            dist,
+            time,
+            max_cap: self.max_cap,
+            pts,
+            points,
+            max_duration: self
+                .max_duration
+                .map_or(u64::MAX, |duration| duration.saturating_mul(MULT)),
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn node(x: f64, dem: i32, service: u64) -> Node {
+        Node {
+            x,
+            dem,
+            due: 100,
+            service,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn euclidean() {
+        let mut builder = DataBuilder::new(10);
+        builder.depot(node(0.0, 0, 0));
+        let (p, d) = builder.request(node(3.0, 5, 2), node(-1.0, -5, 0));
+        let data = builder.build().unwrap();
+
+        assert_eq!((p, d), (1, 2));
+        assert_eq!(data.points, 3);
+        assert_eq!(data.pair_of(p), d);
+        assert!(data.pts[d].is_delivery);
+        assert_eq!(data.dist[data.idx(p, d)], 4 * MULT);
+        assert_eq!(data.time_between(p, d), 6 * MULT);
+        assert_eq!(data.time_between(d, p), 4 * MULT);
+        assert_eq!(data.pts[p].due, 100 * MULT);
+    }
+
+    #[test]
+    fn explicit_matrices() {
+        let mut builder = DataBuilder::new(10);
+        builder.depot(node(0.0, 0, 0));
+        builder.request(node(0.0, 5, 1), node(0.0, -5, 0));
+        builder
+            .distances(vec![0.0, 1.0, 2.0, 3.0, 0.0, 4.0, 5.0, 6.0, 0.0])
+            .travel_times(vec![0.0, 2.0, 4.0, 6.0, 0.0, 8.0, 10.0, 12.0, 0.0]);
+        let data = builder.build().unwrap();
+
+        assert_eq!(data.dist[data.idx(1, 2)], 4 * MULT);
+        assert_eq!(data.dist[data.idx(2, 1)], 6 * MULT);
+        assert_eq!(data.time_between(1, 2), 9 * MULT);
+        assert_eq!(data.time_between(2, 1), 12 * MULT);
+    }
+
+    #[test]
+    fn convention() {
+        let mut builder = DataBuilder::new(10);
+        builder.depot(node(0.0, 0, 0));
+        builder.request(node(1.26, 5, 1), node(0.0, -5, 0));
+        builder.convention(DistanceConvention::OneDecimal);
+        let data = builder.build().unwrap();
+
+        assert_eq!(data.dist[data.idx(0, 1)], 12 * MULT / 10);
+        assert_eq!(data.time_between(1, 0), 22 * MULT / 10);
+
+        builder.convention(DistanceConvention::Integer);
+        let data = builder.build().unwrap();
+
+        assert_eq!(data.dist[data.idx(0, 1)], MULT);
+        assert_eq!(data.time_between(1, 0), 2 * MULT);
+    }
+
+    #[test]
+    fn errors() {
+        let mut builder = DataBuilder::new(10);
+        builder.request(node(0.0, 5, 0), node(0.0, -5, 0));
+        assert!(matches!(builder.build(), Err(DataError::DepotMissing)));
+
+        builder.depot(node(0.0, 0, 0));
+        builder.distances(vec![0.0; 4]);
+        assert!(matches!(
+            builder.build(),
+            Err(DataError::MatrixSize {
+                expected: 9,
+                actual: 4
+            })
+        ));
+
+        let mut builder = DataBuilder::new(10);
+        builder.depot(node(0.0, 0, 0));
+        builder.point(2, node(0.0, -5, 0), 1, true).unwrap();
+        assert!(matches!(builder.build(), Err(DataError::MissingNode(1))));
+        assert!(matches!(
+            builder.point(2, node(0.0, -5, 0), 1, true),
+            Err(DataError::DuplicateId(2))
+        ));
+    }
+}
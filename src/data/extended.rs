@@ -0,0 +1,224 @@
+//! Li & Lim instances extended with explicit matrices. After the usual header and node lines
+//! the file may contain a `DISTANCE` section and a `TIME` section, each followed by one
+//! whitespace separated row per node:
+//!
+//! ```text
+//! 25 200 1
+//! 0 40 50 0 0 1236 0 0 0
+//! ...
+//! DISTANCE
+//! 0 12.5 ...
+//! ...
+//! TIME
+//! 0 15 ...
+//! ...
+//! ```
+//!
+//! Travel times do not include service times, those are still taken from the node lines. A
+//! fourth number in the header, after the speed, limits the duration of every route, with or
+//! without matrices.
+
+use super::builder::{DataBuilder, Node};
+use super::DataError;
+
+pub const DISTANCE: &str = "DISTANCE";
+pub const TIME: &str = "TIME";
+
+pub fn has_matrices(contents: &str) -> bool {
+    contents
+        .lines()
+        .any(|l| matches!(l.trim(), DISTANCE | TIME))
+}
+
+enum Section {
+    Nodes,
+    Distance,
+    Time,
+}
+
+pub fn parse(contents: &str) -> Result<DataBuilder, DataError> {
+    let mut lines = contents
+        .lines()
+        .enumerate()
+        .map(|(i, l)| (i + 1, l.trim()))
+        .filter(|(_, l)| !l.is_empty());
+
+    let (line, header) = lines
+        .next()
+        .ok_or_else(|| DataError::Parse("empty instance".to_string()))?;
+    let header = numbers(line, header)?;
+    let max_cap = *header
+        .get(1)
+        .ok_or_else(|| parse_error(line, "expected vehicles and capacity"))?
+        as i32;
+
+    let mut builder = DataBuilder::new(max_cap);
+    if let Some(max_duration) = max_duration(contents)? {
+        builder.max_duration(max_duration);
+    }
+    let mut nodes = vec![];
+    let mut dist = vec![];
+    let mut time = vec![];
+    let mut section = Section::Nodes;
+
+    for (line, l) in lines {
+        match l {
+            DISTANCE => section = Section::Distance,
+            TIME => section = Section::Time,
+            _ => {
+                let row = numbers(line, l)?;
+                match section {
+                    Section::Nodes => nodes.push((line, row)),
+                    Section::Distance => dist.extend(row),
+                    Section::Time => time.extend(row),
+                }
+            }
+        }
+    }
+
+    let points = nodes.len();
+    for (line, row) in nodes {
+        if row.len() != 9 {
+            return Err(parse_error(line, "expected 9 values"));
+        }
+
+        let id = row[0] as usize;
+        if id >= points {
+            return Err(DataError::IdOutOfRange { id, points });
+        }
+
+        let (p, d) = (row[7] as usize, row[8] as usize);
+        let pair = if p == 0 { d } else { p };
+
+        let node = Node {
+            x: row[1],
+            y: row[2],
+            dem: row[3] as i32,
+            start: row[4] as u64,
+            due: row[5] as u64,
+            service: row[6] as u64,
+        };
+
+        builder.point(id, node, pair, d == 0)?;
+    }
+
+    if !dist.is_empty() {
+        builder.distances(dist);
+    }
+    if !time.is_empty() {
+        builder.travel_times(time);
+    }
+
+    Ok(builder)
+}
+
+/// The fourth number of the header, which plain Li & Lim files may have as well.
+pub fn max_duration(contents: &str) -> Result<Option<u64>, DataError> {
+    let Some((line, header)) = contents
+        .lines()
+        .enumerate()
+        .find(|(_, l)| !l.trim().is_empty())
+    else {
+        return Ok(None);
+    };
+
+    let line = line + 1;
+    let Some(&max_duration) = numbers(line, header.trim())?.get(3) else {
+        return Ok(None);
+    };
+    if max_duration < 0.0 || max_duration.fract() != 0.0 {
+        return Err(parse_error(
+            line,
+            "expected a whole, non-negative maximum route duration",
+        ));
+    }
+
+    Ok(Some(max_duration as u64))
+}
+
+fn numbers(line: usize, l: &str) -> Result<Vec<f64>, DataError> {
+    l.split_whitespace()
+        .map(|x| {
+            x.parse::<f64>()
+                .map_err(|e| parse_error(line, &format!("{x:?}: {e}")))
+        })
+        .collect()
+}
+
+fn parse_error(line: usize, msg: &str) -> DataError {
+    DataError::Parse(format!("line {line}: {msg}"))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::data::MULT;
+
+    const INSTANCE: &str = "\
+        2 10 1
+        0 0 0 0 0 100 0 0 0
+        1 0 0 5 0 100 2 0 2
+        2 0 0 -5 0 100 0 1 0
+        DISTANCE
+        0 1 2
+        3 0 4
+        5 6 0
+        TIME
+        0 1.5 3
+        4 0 5
+        6 7 0
+    ";
+
+    #[test]
+    fn matrices() {
+        assert!(has_matrices(INSTANCE));
+
+        let data = parse(INSTANCE).unwrap().build().unwrap();
+
+        assert_eq!(data.points, 3);
+        assert_eq!(data.max_cap, 10);
+        assert_eq!(data.pair_of(1), 2);
+        assert!(data.pts[2].is_delivery);
+        assert_eq!(data.dist[data.idx(1, 2)], 4 * MULT);
+        assert_eq!(data.dist[data.idx(2, 1)], 6 * MULT);
+        assert_eq!(data.time_between(0, 1), 15 * MULT / 10);
+        assert_eq!(data.time_between(1, 2), 7 * MULT);
+        assert_eq!(data.time_between(2, 1), 7 * MULT);
+        assert_eq!(data.time_between(1, 0), 6 * MULT);
+        assert!(!data.limits_duration());
+
+        let limited = INSTANCE.replacen("2 10 1", "2 10 1 480", 1);
+        let data = parse(&limited).unwrap().build().unwrap();
+        assert_eq!(data.max_duration, 480 * MULT);
+    }
+
+    #[test]
+    fn errors() {
+        assert!(!has_matrices("2 10 1\n0 0 0 0 0 100 0 0 0\n"));
+        for header in ["2 10 1 -480", "2 10 1 480.5"] {
+            assert!(matches!(
+                parse(&INSTANCE.replacen("2 10 1", header, 1)),
+                Err(DataError::Parse(_))
+            ));
+        }
+        assert!(matches!(
+            parse("2 10 1\n0 0 0 0 0 100 0 0\nDISTANCE\n0\n"),
+            Err(DataError::Parse(_))
+        ));
+        assert!(matches!(
+            parse("2 10 1\n0 0 0 0 0 100 0 0 0\n1 0 0 5 0 100 0 0 2\nTIME\n0\n")
+                .unwrap()
+                .build(),
+            Err(DataError::IdOutOfRange { id: 2, points: 2 })
+        ));
+        assert!(matches!(
+            parse("2 10 1\n0 0 0 0 0 100 0 0 0\nTIME\n0 1\n")
+                .unwrap()
+                .build(),
+            Err(DataError::MatrixSize {
+                expected: 1,
+                actual: 2
+            })
+        ));
+    }
+}
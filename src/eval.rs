@@ -1,10 +1,14 @@
-use crate::data::{idx, Data};
+use crate::data::Data;
 
 pub struct Eval {
     pub node: usize,
     pub distance: u64,
     pub time: u64,
     pub capacity: i32,
+    /// the latest the vehicle could have left the depot and still be where it is by now
+    pub departure: u64,
+    /// how much later the customers served so far could still have been served
+    pub slack: u64,
 }
 
 impl Eval {
@@ -14,6 +18,8 @@ impl Eval {
             distance: 0,
             time: 0,
             capacity: 0,
+            departure: 0,
+            slack: u64::MAX,
         }
     }
 
@@ -22,16 +28,22 @@ impl Eval {
         self.distance = other.distance;
         self.time = other.time;
         self.capacity = other.capacity;
+        self.departure = other.departure;
+        self.slack = other.slack;
     }
 
     pub fn next(&mut self, next_node: usize, data: &Data) {
         let nn = &data.pts[next_node];
-        let i = idx(self.node, next_node);
+        let i = data.idx(self.node, next_node);
         self.distance += data.dist[i];
-        self.time += data.time[i];
-        self.time = self.time.max(nn.start);
+        let arrival = self.time + data.time[i];
+        self.time = arrival.max(nn.start);
         self.capacity += nn.dem;
 
+        // leaving the depot later takes up waiting, as far as the earlier customers allow
+        let later = (self.time - arrival).min(self.slack);
+        self.departure += later;
+        self.slack = (self.slack - later).min(nn.due.saturating_sub(self.time));
         self.node = next_node;
     }
 
@@ -62,14 +74,50 @@ impl Eval {
         // constraint as well
     }
 
+    /// Whether the route stays within the maximum duration with the delivery inserted before
+    /// `next_node_id`. From `next_node_id` the vehicle needs `remaining_time_from_next` to get
+    /// back to the depot, but cannot be back before `earliest_return_from_next`; leaving the
+    /// depot later skips waiting on the way back, as far as both the customers served so far
+    /// and the latest feasible departure from `next_node_id` allow.
+    pub fn can_delivery_return_in_time(
+        &self,
+        inserted_node_id: usize,
+        next_node_id: usize,
+        data: &Data,
+        latest_feasible_departure_from_next: u64,
+        remaining_time_from_next: u64,
+        earliest_return_from_next: u64,
+    ) -> bool {
+        let mut inserted = Eval::new();
+        inserted.reset_to(self);
+        inserted.next(inserted_node_id, data);
+
+        let next_arrival = inserted.time + data.time_between(inserted_node_id, next_node_id);
+        let later = inserted
+            .slack
+            .min(latest_feasible_departure_from_next.saturating_sub(next_arrival));
+        let back = (next_arrival + remaining_time_from_next)
+            .max(earliest_return_from_next.saturating_sub(later));
+
+        back - inserted.departure <= data.max_duration
+    }
+
     pub fn is_feasible(&self, data: &Data) -> bool {
         let node = &data.pts[self.node];
 
-        self.time <= node.due && self.capacity <= data.max_cap
+        self.time <= node.due && self.capacity <= data.max_cap && !self.takes_too_long(data)
         // && self.cap >= 0 this chceck is unnecessary because pickups are always before deliveries
     }
 
     pub fn arrives_too_late(&self, data: &Data) -> bool {
         self.time > data.pts[self.node].due
     }
+
+    pub fn takes_too_long(&self, data: &Data) -> bool {
+        self.duration() > data.max_duration
+    }
+
+    pub fn duration(&self) -> u64 {
+        self.time - self.departure
+    }
 }
@@ -1,7 +1,10 @@
+use rand::{Rng, SeedableRng};
+use std::collections::BTreeSet;
+
 use crate::data::Data;
 use crate::eval::Eval;
 use crate::mov::{Between, Move, Swap};
-use crate::{sol::Sol, UNSERVED};
+use crate::{sol::Sol, Prng, UNSERVED};
 
 use self::comb::Combinations;
 
@@ -11,14 +14,29 @@ pub struct Evaluator<'a> {
     data: &'a Data,
     combinations: Combinations,
     pickup_idx: usize,
+    rng: Prng,
+    /// evaluators of the other threads in `pick_over_routes`, with their own scratch buffers
+    helpers: Vec<Evaluator<'a>>,
 }
 
 impl<'a> Evaluator<'a> {
     pub fn new(data: &'a Data) -> Self {
         Self {
             data,
-            combinations: Combinations::new(),
+            combinations: Combinations::new(data.points),
             pickup_idx: UNSERVED,
+            rng: Prng::from_entropy(),
+            helpers: Vec::new(),
+        }
+    }
+
+    /// Number of threads evaluating the routes in `pick_over_routes`, taken from the global rayon
+    /// pool, so at most as many as it has.
+    pub fn set_threads(&mut self, threads: usize) {
+        let helpers = threads.max(1) - 1;
+        self.helpers.truncate(helpers);
+        while self.helpers.len() < helpers {
+            self.helpers.push(Evaluator::new(self.data));
         }
     }
 
@@ -33,9 +51,84 @@ impl<'a> Evaluator<'a> {
         self.pickup_idx = pickup_idx;
     }
 
+    pub fn rng(&mut self) -> &mut Prng {
+        &mut self.rng
+    }
+
+    /// Checks every route and picks one of the moves found, each feasible insertion being equally
+    /// likely. With more than one thread the routes are split among the helpers, each seeded
+    /// from our generator, so the choice stays uniform and follows from the seed.
+    pub fn pick_over_routes<F>(&mut self, routes: &BTreeSet<usize>, check: F) -> Option<Move>
+    where
+        F: Fn(&mut Evaluator<'a>, usize) -> Option<Move> + Sync,
+    {
+        if self.helpers.is_empty() {
+            return self.pick_over(routes.iter().copied(), &check);
+        }
+
+        let routes: Vec<usize> = routes.iter().copied().collect();
+        let chunk = routes.len().div_ceil(self.helpers.len() + 1).max(1);
+        let mut chunks = routes.chunks(chunk);
+        let own = chunks.next().unwrap_or_default();
+
+        let mut helpers = std::mem::take(&mut self.helpers);
+        for helper in helpers.iter_mut() {
+            helper.reset(self.pickup_idx);
+            helper.rng = Prng::seed_from_u64(self.rng.gen());
+        }
+
+        // the threads of the global pool are kept between calls, which come thousands a second
+        let check = &check;
+        let mut picked_by_helpers = vec![None; helpers.len()];
+        let mut picked = None;
+        rayon::scope(|s| {
+            let jobs = helpers.iter_mut().zip(chunks);
+            for ((helper, chunk), helper_picked) in jobs.zip(picked_by_helpers.iter_mut()) {
+                s.spawn(move |_| *helper_picked = helper.pick_over(chunk.iter().copied(), check));
+            }
+
+            picked = self.pick_over(own.iter().copied(), check);
+        });
+
+        for mov in picked_by_helpers.into_iter().flatten() {
+            picked = Some(match picked {
+                Some(p) => p.pick2(mov, &mut self.rng),
+                None => mov,
+            });
+        }
+
+        self.helpers = helpers;
+        picked
+    }
+
+    fn pick_over<I, F>(&mut self, routes: I, check: &F) -> Option<Move>
+    where
+        I: Iterator<Item = usize>,
+        F: Fn(&mut Evaluator<'a>, usize) -> Option<Move>,
+    {
+        let mut picked: Option<Move> = None;
+
+        for route in routes {
+            if let Some(mov) = check(self, route) {
+                picked = Some(match picked {
+                    Some(p) => p.pick2(mov, &mut self.rng),
+                    None => mov,
+                });
+            }
+        }
+
+        picked
+    }
+
     pub fn check_add_to_route(&mut self, sol: &Sol, start: usize) -> Option<Move> {
         let mut iterator = sol.route_iter(start);
-        let mov = self.check_insertions_into_route(self.pickup_idx, &mut iterator, sol);
+        let mov = Self::check_insertions_into_route(
+            self.data,
+            &mut self.rng,
+            self.pickup_idx,
+            &mut iterator,
+            sol,
+        );
 
         mov.is_not_empty().then_some(mov)
     }
@@ -69,7 +162,13 @@ impl<'a> Evaluator<'a> {
             .route_iter(route_of_to_remove)
             .filter(|&x| x != to_remove && x != to_remove_pair);
 
-        self.check_insertions_into_route(to_insert, &mut route_iterator, sol)
+        Self::check_insertions_into_route(
+            self.data,
+            &mut self.rng,
+            to_insert,
+            &mut route_iterator,
+            sol,
+        )
     }
 
     pub fn check_add_to_route_with_k_removed(
@@ -83,7 +182,7 @@ impl<'a> Evaluator<'a> {
         self.combinations
             .k_combinations_of_route(sol, route_start, k);
 
-        let pickups_in_route = self.combinations.route_len / 2;
+        let pickups_in_route = self.combinations.pickups_len();
         let not_removing_whole_route = k < pickups_in_route;
         if not_removing_whole_route {
             let pickup_removed_times = sol.removed_times(self.pickup_idx);
@@ -97,7 +196,9 @@ impl<'a> Evaluator<'a> {
             };
 
             while ok {
-                let mut m = self.check_insertions_into_route(
+                let mut m = Self::check_insertions_into_route(
+                    self.data,
+                    &mut self.rng,
                     self.pickup_idx,
                     &mut self.combinations.into_iter(),
                     sol,
@@ -110,7 +211,7 @@ impl<'a> Evaluator<'a> {
                         m.removed[i] = removed_pickups[removed_idxs[i]].idx();
                     }
                     // m.removed[..k].copy_from_slice(self.combinations.removed());
-                    mov.pick(&m)
+                    mov.pick(&m, &mut self.rng)
                 }
 
                 ok = self
@@ -122,15 +223,67 @@ impl<'a> Evaluator<'a> {
         mov.is_not_empty().then_some(mov)
     }
 
+    /// Cheapest (by distance) insertion of the pickup and its delivery into the route, with
+    /// the increase in distance it causes.
+    pub fn check_cheapest_insertion(&mut self, sol: &Sol, start: usize) -> Option<(i64, Move)> {
+        let pickup = self.pickup_idx;
+        let delivery = sol.data.pair_of(pickup);
+        let data = self.data;
+        let added = |before: usize, node: usize, after: usize| {
+            data.dist_between(before, node) as i64 + data.dist_between(node, after) as i64
+                - data.dist_between(before, after) as i64
+        };
+
+        let mut best: Option<(i64, Move)> = None;
+        let mut iterator = sol.route_iter(start);
+        Self::for_each_feasible_insertion(data, pickup, &mut iterator, sol, |p, d| {
+            // when the delivery directly follows the pickup, d.0 is the pickup and the edge
+            // between the pickup and p.1 added for the pickup is taken away again
+            let delta = added(p.0, pickup, p.1) + added(d.0, delivery, d.1);
+            let better = match best {
+                Some((b, _)) => delta < b,
+                None => true,
+            };
+            if better {
+                let mut mov = Move::new(pickup);
+                mov.put_pickup_between = *p;
+                mov.put_delivery_between = *d;
+                mov.times = 1;
+                best = Some((delta, mov));
+            }
+        });
+
+        best
+    }
+
     fn check_insertions_into_route<ClonableIterator: Iterator<Item = usize> + Clone>(
-        &self,
+        data: &Data,
+        rng: &mut Prng,
         pickup: usize,
         pickup_iterator: &mut ClonableIterator,
         sol: &Sol,
     ) -> Move {
         let mut mov = Move::new(pickup);
+        Self::for_each_feasible_insertion(data, pickup, pickup_iterator, sol, |p, d| {
+            mov.maybe_switch(p, d, rng)
+        });
+
+        mov
+    }
+
+    fn for_each_feasible_insertion<ClonableIterator, F>(
+        data: &Data,
+        pickup: usize,
+        pickup_iterator: &mut ClonableIterator,
+        sol: &Sol,
+        mut feasible: F,
+    ) where
+        ClonableIterator: Iterator<Item = usize> + Clone,
+        F: FnMut(&Between, &Between),
+    {
         let delivery_idx = sol.data.pair_of(pickup);
-        let delivery_due = self.data.pts[delivery_idx].due;
+        let delivery_due = data.pts[delivery_idx].due;
+        let limited = data.limits_duration();
 
         let mut before_pickup = 0;
         let mut normal_route_eval = Eval::new();
@@ -138,25 +291,35 @@ impl<'a> Evaluator<'a> {
 
         while let Some(after_pickup) = pickup_iterator.next() {
             insertion_eval.reset_to(&normal_route_eval);
-            insertion_eval.next(pickup, self.data);
+            insertion_eval.next(pickup, data);
 
-            if insertion_eval.arrives_too_late(self.data) {
+            if insertion_eval.arrives_too_late(data) {
                 break;
             }
 
-            if insertion_eval.is_feasible(self.data) {
+            if insertion_eval.is_feasible(data) {
                 let mut delivery_iterator = pickup_iterator.clone();
 
                 let mut before_delivery = pickup;
                 let mut after_delivery = after_pickup;
-                while before_delivery != 0 && insertion_eval.is_feasible(self.data) {
-                    if insertion_eval.can_delivery_be_inserted(
+                while before_delivery != 0 && insertion_eval.is_feasible(data) {
+                    let latest = sol.latest_feasible_departure[after_delivery];
+                    let feasible_insertion = insertion_eval.can_delivery_be_inserted(
                         delivery_idx,
                         after_delivery,
-                        self.data,
-                        sol.latest_feasible_departure[after_delivery],
-                    ) {
-                        mov.maybe_switch(
+                        data,
+                        latest,
+                    ) && (!limited
+                        || insertion_eval.can_delivery_return_in_time(
+                            delivery_idx,
+                            after_delivery,
+                            data,
+                            latest,
+                            sol.remaining_time[after_delivery],
+                            sol.earliest_return[after_delivery],
+                        ));
+                    if feasible_insertion {
+                        feasible(
                             &Between(before_pickup, after_pickup),
                             &Between(before_delivery, after_delivery),
                         );
@@ -167,16 +330,14 @@ impl<'a> Evaluator<'a> {
                         break;
                     }
 
-                    insertion_eval.next(after_delivery, self.data);
+                    insertion_eval.next(after_delivery, data);
                     before_delivery = after_delivery;
                     after_delivery = delivery_iterator.next().unwrap_or(0);
                 }
             }
 
-            normal_route_eval.next(after_pickup, self.data);
+            normal_route_eval.next(after_pickup, data);
             before_pickup = after_pickup;
         }
-
-        mov
     }
 }
@@ -2,7 +2,6 @@ use std::ops::Not;
 
 use itertools::Itertools;
 
-use crate::data::PTS;
 use crate::sol::Sol;
 use crate::{K_MAX, UNSERVED};
 
@@ -44,16 +43,18 @@ pub struct Combinations {
     pub k: usize,
     pub route_len: usize,
     pub cur_removed_times_total: u64,
-    pub route: [PairInfo; PTS],
-    pub route_position: [usize; PTS],
-    pub pickups: [PairInfo; PTS / 2],
+    pub route: Vec<PairInfo>,
+    pub route_position: Vec<usize>,
+    pub pickups: Vec<PairInfo>,
     pub combination_indices: [usize; K_MAX + 1],
-    pub sum_of_next: [[u64; K_MAX + 1]; PTS],
+    pub sum_of_next: Vec<[u64; K_MAX + 1]>,
 }
 
 impl Combinations {
-    pub fn new() -> Self {
-        let mut route = [Default::default(); PTS];
+    pub fn new(points: usize) -> Self {
+        // the leading and trailing depot around at most points - 1 customers, plus one slot
+        // past the end that the iterator reads eagerly
+        let mut route = vec![Default::default(); points + 2];
         route[0] = PairInfo::new(0, 0, 0, 0);
 
         Self {
@@ -61,10 +62,11 @@ impl Combinations {
             route_len: 0,
             cur_removed_times_total: 0,
             route,
-            route_position: [UNSERVED; PTS],
-            pickups: [Default::default(); PTS / 2],
+            route_position: vec![UNSERVED; points],
+            // the first k are read before it is known whether the route has that many
+            pickups: vec![Default::default(); (points / 2 + 1).max(K_MAX)],
             combination_indices: [UNSERVED; K_MAX + 1],
-            sum_of_next: [[u64::max_value(); K_MAX + 1]; PTS],
+            sum_of_next: vec![[u64::max_value(); K_MAX + 1]; points],
         }
     }
 
@@ -135,7 +137,7 @@ impl Combinations {
         }
     }
 
-    fn pickups_len(&mut self) -> usize {
+    pub fn pickups_len(&mut self) -> usize {
         (self.route_len - 2) / 2
     }
 
@@ -220,7 +222,7 @@ impl Combinations {
     }
 
     pub fn pickups(&self) -> &[PairInfo] {
-        &self.pickups[0..PTS / 2]
+        &self.pickups
     }
 }
 
@@ -306,13 +308,16 @@ mod test {
     fn prep() -> Data {
         let points = 13;
 
-        let mut pts = [PointData {
-            dem: 0,
-            start: 0,
-            due: 0,
-            pair: 0,
-            is_delivery: false,
-        }; PTS];
+        let mut pts = vec![
+            PointData {
+                dem: 0,
+                start: 0,
+                due: 0,
+                pair: 0,
+                is_delivery: false,
+            };
+            points
+        ];
         for (pickup, delivery) in (1..points).tuples() {
             pts[pickup].pair = delivery;
             pts[pickup].is_delivery = false;
@@ -320,13 +325,14 @@ mod test {
             pts[delivery].is_delivery = true;
         }
 
-        let matrix = vec![0; PTS * PTS];
+        let matrix = vec![0; points * points];
         Data {
             dist: matrix.clone(),
             pts,
             max_cap: 0,
             time: matrix.clone(),
             points,
+            max_duration: u64::MAX,
         }
     }
 
@@ -345,7 +351,7 @@ mod test {
             assert_eq!(sol.removed_times(i), i as u64);
         }
 
-        let mut c = Combinations::new();
+        let mut c = Combinations::new(data.points);
         sol.add_route(&vec![0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 0]);
         c.k_combinations_of_route(&sol, 1, 1);
 
@@ -397,6 +403,17 @@ mod test {
         assert!(!c.next_combination_with_lower_score(11));
     }
 
+    #[test]
+    fn more_removed_than_the_route_has() {
+        let data = prep();
+        let mut sol = Sol::new(&data);
+        sol.add_route(&vec![0, 1, 2, 0]);
+
+        let mut c = Combinations::new(data.points);
+        c.k_combinations_of_route(&sol, 1, K_MAX);
+        assert_eq!(c.pickups_len(), 1);
+    }
+
     #[test]
     fn second() {
         let data = prep();
@@ -405,7 +422,7 @@ mod test {
 
         sol.add_route(&vec![0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 0]);
 
-        let mut c = Combinations::new();
+        let mut c = Combinations::new(data.points);
         c.k_combinations_of_route(&sol, 1, 1);
 
         assert_eq!(c.k, 1);
@@ -428,7 +445,7 @@ mod test {
 
         dbg!("k = 2");
 
-        let mut c = Combinations::new();
+        let mut c = Combinations::new(data.points);
         c.k_combinations_of_route(&sol, 1, 2);
 
         assert_eq!(c.k, 2);
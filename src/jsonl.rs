@@ -0,0 +1,250 @@
+use serde::Serialize;
+use std::io::Write;
+
+use crate::bks::{BestKnown, Gap};
+use crate::observer::GesObserver;
+use crate::sol::cost::Cost;
+use crate::sol::Sol;
+use crate::stats::Stats;
+
+/// One line of the run log. Times are in seconds, distances in instance units.
+#[derive(Serialize, Debug, PartialEq)]
+#[serde(tag = "event", rename_all = "snake_case")]
+pub enum Event {
+    Start {
+        instance: String,
+        points: usize,
+        target_routes: usize,
+        lower_bound: usize,
+        max_time: f64,
+        seed: Option<u64>,
+    },
+    RouteRemoved {
+        routes: usize,
+        ejected: usize,
+        total_iterations: usize,
+        time: f64,
+    },
+    Stats {
+        routes: usize,
+        ejected: usize,
+        iterations: usize,
+        total_iterations: usize,
+        heap_min: usize,
+        heap_max: usize,
+        route_time: f64,
+        time: f64,
+    },
+    NewBest {
+        routes: usize,
+        iterations: usize,
+        total_iterations: usize,
+        route_time: f64,
+        time: f64,
+    },
+    Result {
+        routes: usize,
+        unserved: usize,
+        distance: f64,
+        duration: f64,
+        waiting: f64,
+        total_iterations: usize,
+        time: f64,
+    },
+    BestKnown {
+        best: BestKnown,
+        gap: Gap,
+    },
+}
+
+impl Event {
+    pub fn result(solution: &Sol, cost: &Cost, stats: &Stats) -> Self {
+        Event::Result {
+            routes: solution.routes_number(),
+            unserved: solution.heap.size,
+            distance: cost.distance,
+            duration: cost.duration,
+            waiting: cost.waiting,
+            total_iterations: stats.iterations().total(),
+            time: stats.total_time().as_secs_f64(),
+        }
+    }
+
+    /// Writes the event as a single line of JSON, in one write so that lines of searches logging
+    /// to the same output side by side do not mix.
+    pub fn write<W: Write>(&self, w: &mut W) -> std::io::Result<()> {
+        write_line(self, w)
+    }
+}
+
+/// An event of one of several searches, e.g. those of a portfolio.
+#[derive(Serialize)]
+struct SearchEvent<'a> {
+    search: usize,
+    #[serde(flatten)]
+    event: &'a Event,
+}
+
+fn write_line<T: Serialize, W: Write>(value: &T, w: &mut W) -> std::io::Result<()> {
+    let mut line = serde_json::to_vec(value)?;
+    line.push(b'\n');
+    w.write_all(&line)?;
+    w.flush()
+}
+
+/// Logs the progress of `Ges` as JSON lines, with periodic statistics every `every` iterations
+/// of a route removal.
+pub struct JsonlObserver<W: Write> {
+    writer: W,
+    every: usize,
+    search: Option<usize>,
+}
+
+impl<W: Write> JsonlObserver<W> {
+    pub fn new(writer: W) -> Self {
+        Self {
+            writer,
+            every: 10000,
+            search: None,
+        }
+    }
+
+    pub fn every(mut self, iterations: usize) -> Self {
+        self.every = iterations.max(1);
+        self
+    }
+
+    /// Marks every event with the index of the search, for searches logging side by side.
+    pub fn search(mut self, index: usize) -> Self {
+        self.search = Some(index);
+        self
+    }
+
+    fn write(&mut self, event: Event) {
+        let _ = match self.search {
+            Some(search) => write_line(
+                &SearchEvent {
+                    search,
+                    event: &event,
+                },
+                &mut self.writer,
+            ),
+            None => event.write(&mut self.writer),
+        };
+    }
+}
+
+impl<W: Write> GesObserver for JsonlObserver<W> {
+    fn on_route_removed(&mut self, solution: &Sol, stats: &Stats) {
+        self.write(Event::RouteRemoved {
+            routes: solution.routes_number(),
+            ejected: solution.heap.size,
+            total_iterations: stats.iterations().total(),
+            time: stats.total_time().as_secs_f64(),
+        });
+    }
+
+    fn on_new_best(&mut self, solution: &Sol, stats: &Stats) {
+        self.write(Event::NewBest {
+            routes: solution.routes_number(),
+            iterations: stats.iterations().current(),
+            total_iterations: stats.iterations().total(),
+            route_time: stats.route_time().as_secs_f64(),
+            time: stats.total_time().as_secs_f64(),
+        });
+    }
+
+    fn on_iteration(&mut self, solution: &Sol, stats: &Stats) {
+        if stats.iterations().current() % self.every != 0 {
+            return;
+        }
+
+        self.write(Event::Stats {
+            routes: solution.routes_number(),
+            ejected: solution.heap.size,
+            iterations: stats.iterations().current(),
+            total_iterations: stats.iterations().total(),
+            heap_min: stats.heap_size().min(),
+            heap_max: stats.heap_size().max(),
+            route_time: stats.route_time().as_secs_f64(),
+            time: stats.total_time().as_secs_f64(),
+        });
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::tests::{instance, short_conf};
+    use crate::{Conf, Ges, LogFormat};
+    use std::sync::{Arc, Mutex};
+
+    #[derive(Clone, Default)]
+    struct Shared(Arc<Mutex<Vec<u8>>>);
+
+    impl Write for Shared {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            self.0.lock().unwrap().write(buf)
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn one_object_per_line() {
+        let data = instance(20);
+        let mut sol = Sol::new(&data);
+        sol.initialize();
+
+        let out = Shared::default();
+        let mut ges = Ges::new(&data);
+        ges.add_observer(Box::new(JsonlObserver::new(out.clone()).every(1)));
+        let conf = Conf {
+            seed: Some(2),
+            log_format: LogFormat::Jsonl,
+            ..short_conf(5)
+        };
+        ges.ges(&mut sol, conf);
+
+        let mut w = out.clone();
+        Event::result(&sol, &sol.cost(), ges.stats())
+            .write(&mut w)
+            .unwrap();
+
+        let bytes = out.0.lock().unwrap().clone();
+        let lines: Vec<serde_json::Value> = String::from_utf8(bytes)
+            .unwrap()
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+
+        let count = |event: &str| lines.iter().filter(|l| l["event"] == event).count();
+        assert_eq!(count("route_removed"), 15);
+        assert_eq!(count("new_best"), 15);
+        assert!(count("stats") > 0);
+
+        let last = lines.last().unwrap();
+        assert_eq!(last["event"], "result");
+        assert_eq!(last["routes"], 5);
+        assert_eq!(last["unserved"], 0);
+    }
+
+    #[test]
+    fn marks_the_search() {
+        let data = instance(2);
+        let mut sol = Sol::new(&data);
+        sol.initialize();
+
+        let out = Shared::default();
+        let mut observer = JsonlObserver::new(out.clone()).search(3);
+        observer.on_route_removed(&sol, &Stats::new());
+
+        let bytes = out.0.lock().unwrap().clone();
+        let line: serde_json::Value = serde_json::from_slice(&bytes).unwrap();
+        assert_eq!(line["search"], 3);
+        assert_eq!(line["event"], "route_removed");
+        assert_eq!(line["routes"], sol.routes_number());
+    }
+}
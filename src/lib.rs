@@ -1,13 +1,31 @@
+use checkpoint::Checkpoint;
 use data::Data;
 use evaluator::Evaluator;
+use itertools::Itertools;
+use observer::GesObserver;
+use rand::seq::SliceRandom;
+use rand::SeedableRng;
+use rand_xoshiro::Xoshiro256PlusPlus;
+use sol::snapshot::{SnapshotError, SolSnapshot};
 use sol::Sol;
 use stats::Stats;
-use std::time::Duration;
+use std::path::PathBuf;
+use std::str::FromStr;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
 
+pub mod batch;
+pub mod bks;
+pub mod bounds;
+pub mod checkpoint;
 pub mod data;
 pub mod eval;
 pub mod evaluator;
+pub mod jsonl;
 pub mod mov;
+pub mod observer;
+pub mod portfolio;
 pub mod routes;
 pub mod sol;
 pub mod stats;
@@ -15,12 +33,19 @@ pub mod stats;
 const UNSERVED: usize = usize::MAX;
 pub const K_MAX: usize = 10;
 
+pub type Prng = Xoshiro256PlusPlus;
+
 pub struct Ges<'a> {
     evaluator: Evaluator<'a>,
     stats: Stats,
+    interrupt: Option<Arc<AtomicBool>>,
+    observers: Vec<Box<dyn GesObserver + Send + 'a>>,
+    /// the solution before the current route removal
+    complete: Option<SolSnapshot>,
+    last_checkpoint: Instant,
 }
 
-#[derive(Default)]
+#[derive(Default, Clone, Copy)]
 pub enum Log {
     #[default]
     Quiet,
@@ -28,10 +53,55 @@ pub enum Log {
     Extra,
 }
 
+/// Whether the search prints its progress as text; otherwise it prints nothing itself and with
+/// `Jsonl` leaves reporting to a `jsonl::JsonlObserver`.
+#[derive(Default, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum LogFormat {
+    #[default]
+    Text,
+    Jsonl,
+    Silent,
+}
+
+impl FromStr for LogFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "text" => Ok(LogFormat::Text),
+            "jsonl" => Ok(LogFormat::Jsonl),
+            "silent" => Ok(LogFormat::Silent),
+            _ => Err(format!(
+                "unknown log format {s:?}, expected one of: text, jsonl, silent"
+            )),
+        }
+    }
+}
+
+#[derive(Clone)]
 pub struct Conf {
     pub max_optimization_time: Duration,
     pub target_routes: usize,
     pub log: Log,
+    pub minimize_distance: bool,
+    pub seed: Option<u64>,
+    /// number of independent searches run by `portfolio::solve`
+    pub threads: usize,
+    /// restart a portfolio search from the best solution once it has this many routes more
+    pub restart_lag: Option<usize>,
+    /// number of threads checking insertions into the routes, worth it for large instances only
+    pub insertion_threads: usize,
+    /// stop after this many iterations in total
+    pub max_iterations: Option<usize>,
+    /// give up a route removal, and so end the search, after this many iterations
+    pub max_route_iterations: Option<usize>,
+    /// give up a route removal, and so end the search, when the number of ejected pairs has not
+    /// reached a new low for this many iterations
+    pub stagnation_limit: Option<usize>,
+    pub log_format: LogFormat,
+    /// where `Ges::ges` periodically saves the state of the search, see `Ges::resume`
+    pub checkpoint: Option<PathBuf>,
+    pub checkpoint_every: Duration,
 }
 
 impl Default for Conf {
@@ -40,70 +110,309 @@ impl Default for Conf {
             max_optimization_time: Duration::new(600, 0),
             target_routes: 0,
             log: Default::default(),
+            minimize_distance: true,
+            seed: None,
+            threads: 1,
+            restart_lag: None,
+            insertion_threads: 1,
+            max_iterations: None,
+            max_route_iterations: None,
+            stagnation_limit: None,
+            log_format: LogFormat::Text,
+            checkpoint: None,
+            checkpoint_every: Duration::new(300, 0),
         }
     }
 }
 
+impl Conf {
+    /// Whether there is no route left to remove; the last one never is.
+    pub fn reached_target(&self, routes: usize) -> bool {
+        routes <= self.target_routes.max(1)
+    }
+
+    fn prints_summary(&self) -> bool {
+        self.log_format == LogFormat::Text
+    }
+
+    fn prints_progress(&self) -> bool {
+        self.prints_summary() && !matches!(self.log, Log::Quiet)
+    }
+}
+
 impl<'a> Ges<'a> {
     pub fn new(data: &'a Data) -> Self {
         Self {
             evaluator: Evaluator::new(&data),
             stats: Stats::new(),
+            interrupt: None,
+            observers: Vec::new(),
+            complete: None,
+            last_checkpoint: Instant::now(),
+        }
+    }
+
+    pub fn add_observer(&mut self, observer: Box<dyn GesObserver + Send + 'a>) {
+        self.observers.push(observer);
+    }
+
+    /// Makes all random choices of the search, both in the solution and in the evaluator,
+    /// follow from the seed.
+    pub fn seed(&mut self, solution: &mut Sol, seed: u64) {
+        let mut rng = Prng::seed_from_u64(seed);
+        solution.rng = rng.clone();
+        rng.jump();
+        *self.evaluator.rng() = rng;
+    }
+
+    /// The state of the search, from which `resume` continues it.
+    pub fn checkpoint(&mut self, solution: &Sol) -> Checkpoint {
+        Checkpoint {
+            solution: solution.snapshot(),
+            complete: self.complete.clone().unwrap_or_else(|| solution.snapshot()),
+            solution_rng: solution.rng.clone(),
+            evaluator_rng: self.evaluator.rng().clone(),
+            counters: self.stats.counters(),
         }
     }
 
+    /// Brings the solution, the random number generators and the statistics back to the state of
+    /// the checkpoint. Run `ges` without a seed afterwards to continue the search.
+    pub fn resume(
+        &mut self,
+        solution: &mut Sol,
+        checkpoint: &Checkpoint,
+    ) -> Result<(), SnapshotError> {
+        solution.check_snapshot(&checkpoint.complete)?;
+        solution.restore(&checkpoint.solution)?;
+
+        solution.rng = checkpoint.solution_rng.clone();
+        *self.evaluator.rng() = checkpoint.evaluator_rng.clone();
+        self.stats.resume(&checkpoint.counters);
+        self.complete = Some(checkpoint.complete.clone());
+
+        Ok(())
+    }
+
+    /// Removes routes until the target is reached, a budget runs out or a removal fails. The
+    /// solution is left serving every customer, with the routes of the last successful removal.
+    /// A solution with ejected pairs, as left by `resume`, first gets them reinserted.
     pub fn ges(&mut self, solution: &mut Sol, conf: Conf) {
+        if let Some(seed) = conf.seed {
+            self.seed(solution, seed);
+        }
+        self.last_checkpoint = Instant::now();
+
+        let mut resumed = solution.heap.size > 0;
         loop {
             let routes = solution.routes_number();
 
-            let total = self.stats.total_time();
-
-            if routes <= conf.target_routes || total >= conf.max_optimization_time {
-                self.stats.print_after_route_removal(solution);
-                if !matches!(conf.log, Log::Quiet) {
-                    solution.eprn();
+            if !resumed && (conf.reached_target(routes) || self.out_of_budget(&conf)) {
+                if conf.prints_summary() {
+                    self.stats.print_after_route_removal(solution);
                 }
+                break;
+            }
 
-                if let Log::Extra = conf.log {
-                    for i in 0..solution.data.points {
-                        println!("{} {}", i, solution.heap.removed_times[i]);
-                    }
-                }
+            let removed = if resumed {
+                self.reinsert(solution, &conf)
+            } else {
+                self.complete = Some(solution.snapshot());
+                self.remove_route(solution, &conf)
+            };
+            resumed = false;
 
+            // a failed removal leaves pairs unserved, the caller gets the last complete solution
+            if !removed {
+                if let Some(complete) = self.complete.take() {
+                    solution.restore_unchecked(&complete);
+                }
+                if conf.prints_summary() {
+                    self.stats.print_after_route_removal(solution);
+                }
                 break;
             }
 
-            let random_route_first = solution.random_route_first();
-            solution.remove_route(random_route_first);
+            if conf.prints_progress() {
+                self.stats.print_after_route_removal(solution);
+            }
+        }
 
-            self.stats.reset();
-            self.stats.add_iteration(solution.heap.size);
-            while let Some(top) = solution.top() {
-                let total = self.stats.total_time();
-                if total >= conf.max_optimization_time {
-                    break;
-                }
-                let maybe = solution.try_insert(top, &mut self.evaluator);
-
-                if let Some(mov) = maybe {
-                    solution.pop();
-                    solution.make_move(&mov);
-                    debug_assert!(solution.check_routes());
-                } else {
-                    solution.inc();
-                    for _ in 0..50 {
-                        solution.perturb(&mut self.evaluator);
+        self.finish(solution, &conf);
+    }
+
+    /// Removes a random route and tries to reinsert its pairs into the remaining ones. Returns
+    /// whether it succeeded; otherwise the budget ran out, the removal stagnated or the search was
+    /// interrupted, leaving some pairs unserved.
+    pub fn remove_route(&mut self, solution: &mut Sol, conf: &Conf) -> bool {
+        self.evaluator.set_threads(conf.insertion_threads);
+
+        let random_route_first = solution.random_route_first();
+        solution.remove_route(random_route_first);
+
+        self.stats.reset();
+        for observer in self.observers.iter_mut() {
+            observer.on_route_removed(solution, &self.stats);
+        }
+
+        self.stats.add_iteration(solution.heap.size);
+        self.reinsert(solution, conf)
+    }
+
+    /// Inserts the ejected pairs back, ejecting others and perturbing the solution when needed.
+    /// Returns whether all of them are served again before the budget ran out, the removal
+    /// stagnated or the search was interrupted.
+    pub fn reinsert(&mut self, solution: &mut Sol, conf: &Conf) -> bool {
+        while let Some(top) = solution.top() {
+            if self.out_of_budget(conf) || self.stagnated(conf) {
+                return false;
+            }
+            let maybe = solution.try_insert(top, &mut self.evaluator);
+
+            if let Some(mov) = maybe {
+                solution.pop();
+                solution.make_move(&mov);
+                debug_assert!(solution.check_routes());
+
+                let k = mov.removed.iter().take_while(|&&x| x != 0).count();
+                if k > 0 {
+                    for observer in self.observers.iter_mut() {
+                        observer.on_ejection(mov.pickup, &mov.removed[..k]);
                     }
                 }
+            } else {
+                solution.inc();
+                for _ in 0..50 {
+                    solution.perturb(&mut self.evaluator);
+                }
 
-                self.stats.add_iteration(solution.heap.size);
-                if !matches!(conf.log, Log::Quiet) {
-                    self.stats.print_occasionally(solution);
+                for observer in self.observers.iter_mut() {
+                    observer.on_perturbation(top, solution);
                 }
             }
 
-            if !matches!(conf.log, Log::Quiet) {
-                self.stats.print_after_route_removal(solution);
+            self.stats.add_iteration(solution.heap.size);
+            for observer in self.observers.iter_mut() {
+                observer.on_iteration(solution, &self.stats);
+            }
+            if conf.prints_progress() {
+                self.stats.print_occasionally(solution);
+            }
+            self.checkpoint_if_due(solution, conf);
+        }
+
+        for observer in self.observers.iter_mut() {
+            observer.on_new_best(solution, &self.stats);
+        }
+
+        true
+    }
+
+    /// Shortens the routes if the solution serves every pair, and prints it as configured.
+    pub fn finish(&mut self, solution: &mut Sol, conf: &Conf) {
+        // an interrupted route removal leaves pairs unserved, there is nothing to shorten then
+        if conf.minimize_distance && solution.heap.size == 0 {
+            self.minimize_distance(solution, conf);
+            if conf.prints_summary() {
+                self.stats.print_after_distance_minimization(solution);
+            }
+        }
+
+        if !matches!(conf.log, Log::Quiet) {
+            solution.eprn();
+        }
+
+        if matches!(conf.log, Log::Extra) && conf.prints_summary() {
+            for i in 0..solution.data.points {
+                println!("{} {}", i, solution.heap.removed_times[i]);
+            }
+        }
+    }
+
+    fn checkpoint_if_due(&mut self, solution: &Sol, conf: &Conf) {
+        let Some(path) = conf.checkpoint.as_ref() else {
+            return;
+        };
+        if self.last_checkpoint.elapsed() < conf.checkpoint_every {
+            return;
+        }
+
+        if let Err(e) = self.checkpoint(solution).write(path) {
+            eprintln!("cannot write checkpoint {}: {e}", path.display());
+        }
+        self.last_checkpoint = Instant::now();
+    }
+
+    /// Makes the search stop as soon as the flag is raised, e.g. by a controlling thread.
+    pub fn interrupt_on(&mut self, flag: Arc<AtomicBool>) {
+        self.interrupt = Some(flag);
+    }
+
+    fn interrupted(&self) -> bool {
+        self.interrupt
+            .as_ref()
+            .is_some_and(|flag| flag.load(Ordering::Relaxed))
+    }
+
+    /// Whether the time or the total number of iterations is used up, or the search was
+    /// interrupted.
+    pub fn out_of_budget(&self, conf: &Conf) -> bool {
+        self.interrupted()
+            || self.stats.total_time() >= conf.max_optimization_time
+            || conf
+                .max_iterations
+                .is_some_and(|max| self.stats.iterations().total() >= max)
+    }
+
+    fn stagnated(&self, conf: &Conf) -> bool {
+        let iterations = self.stats.iterations();
+
+        conf.max_route_iterations
+            .is_some_and(|max| iterations.current() >= max)
+            || conf
+                .stagnation_limit
+                .is_some_and(|limit| iterations.since_progress() >= limit)
+    }
+
+    pub fn stats(&self) -> &Stats {
+        &self.stats
+    }
+
+    /// Moves pairs to their cheapest feasible position, keeping the number of routes, until no
+    /// move shortens the solution or the budget runs out.
+    pub fn minimize_distance(&mut self, solution: &mut Sol, conf: &Conf) {
+        self.stats.reset();
+
+        let mut pickups = (1..solution.data.points)
+            .filter(|&i| !solution.data.pts[i].is_delivery)
+            .collect_vec();
+
+        let mut improved = true;
+        while improved {
+            improved = false;
+            pickups.shuffle(self.evaluator.rng());
+
+            for &pickup in pickups.iter() {
+                if self.out_of_budget(conf) {
+                    return;
+                }
+
+                if solution.only_pickup_in_route(pickup) {
+                    continue;
+                }
+
+                let gain = solution.removal_gain(pickup);
+                solution.remove_pair(pickup);
+
+                // the position it was just taken out of is always feasible
+                let (delta, mov) = solution
+                    .try_insert_cheapest(pickup, &mut self.evaluator)
+                    .unwrap();
+                solution.make_move(&mov);
+                debug_assert!(solution.check_routes());
+
+                improved |= delta < gain;
+                self.stats.add_iteration(0);
             }
         }
     }
@@ -111,5 +420,373 @@ impl<'a> Ges<'a> {
 
 #[cfg(test)]
 mod tests {
-    // use super::*;
+    use super::*;
+    use data::builder::{DataBuilder, Node};
+
+    pub fn instance(pairs: usize) -> Data {
+        let node = |i: usize, dem| Node {
+            x: ((i * 37) % 101) as f64,
+            y: ((i * 59) % 103) as f64,
+            dem,
+            start: 0,
+            due: 1000,
+            service: 1,
+        };
+
+        let mut builder = DataBuilder::new(50);
+        builder.depot(node(0, 0));
+        for i in 0..pairs {
+            builder.request(node(2 * i + 1, 10), node(2 * i + 2, -10));
+        }
+
+        builder.build().unwrap()
+    }
+
+    /// Requests without time windows at the depot, each loading 10 of 50.
+    pub fn requests(pairs: usize) -> Data {
+        let node = |dem| Node {
+            dem,
+            due: 1000,
+            ..Default::default()
+        };
+
+        let mut builder = DataBuilder::new(50);
+        builder.depot(node(0));
+        for _ in 0..pairs {
+            builder.request(node(10), node(-10));
+        }
+
+        builder.build().unwrap()
+    }
+
+    /// A search over within seconds that leaves the distance as it is.
+    pub fn short_conf(target_routes: usize) -> Conf {
+        Conf {
+            max_optimization_time: Duration::new(5, 0),
+            target_routes,
+            minimize_distance: false,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn same_seed_same_solution() {
+        let data = instance(20);
+        let run = |seed| {
+            let mut sol = Sol::new(&data);
+            sol.initialize();
+            let conf = Conf {
+                seed: Some(seed),
+                ..short_conf(5)
+            };
+            Ges::new(&data).ges(&mut sol, conf);
+            sol.rs()
+        };
+
+        assert_eq!(run(7), run(7));
+    }
+
+    #[test]
+    fn same_seed_same_solution_with_insertion_threads() {
+        let data = instance(20);
+        let run = |seed| {
+            let mut sol = Sol::new(&data);
+            sol.initialize();
+            let conf = Conf {
+                seed: Some(seed),
+                insertion_threads: 3,
+                ..short_conf(5)
+            };
+            Ges::new(&data).ges(&mut sol, conf);
+            assert!(sol.check_routes());
+            sol.rs()
+        };
+
+        assert_eq!(run(7), run(7));
+    }
+
+    #[test]
+    fn stops_on_iteration_budgets() {
+        let data = instance(20);
+        let run = |conf: Conf| {
+            let mut sol = Sol::new(&data);
+            sol.initialize();
+            let mut ges = Ges::new(&data);
+            ges.ges(&mut sol, conf);
+            (sol.routes_number(), ges.stats().iterations().total())
+        };
+
+        let (_, total) = run(Conf {
+            max_iterations: Some(30),
+            ..short_conf(1)
+        });
+        assert!(total <= 31);
+
+        let (routes, _) = run(Conf {
+            max_route_iterations: Some(1),
+            ..short_conf(1)
+        });
+        assert!(routes > 1);
+
+        let (routes, _) = run(Conf {
+            stagnation_limit: Some(1),
+            ..short_conf(1)
+        });
+        assert!(routes > 1);
+    }
+
+    #[test]
+    fn failed_removal_keeps_every_customer_served() {
+        let data = instance(20);
+        let mut sol = Sol::new(&data);
+        sol.initialize();
+
+        let conf = Conf {
+            max_route_iterations: Some(5),
+            seed: Some(4),
+            ..short_conf(2)
+        };
+        Ges::new(&data).ges(&mut sol, conf);
+
+        assert!(sol.routes_number() > 2);
+        assert_eq!(sol.heap.size, 0);
+        assert!(sol.validate().is_empty());
+    }
+
+    #[test]
+    fn stops_below_the_lower_bound() {
+        // any two of the requests fit into one route, all three do not
+        let node = |x, y, due| Node {
+            x,
+            y,
+            dem: 0,
+            start: 0,
+            due,
+            service: 0,
+        };
+        let mut builder = DataBuilder::new(50);
+        builder.depot(node(0.0, 0.0, 1000));
+        for (x, y) in [(10.0, 0.0), (0.0, 10.0), (-10.0, 0.0)] {
+            builder.request(node(x, y, 31), node(x, y, 31));
+        }
+        let data = builder.build().unwrap();
+        assert_eq!(bounds::Bounds::new(&data).routes(), 1);
+
+        let mut sol = Sol::new(&data);
+        sol.initialize();
+        let conf = Conf {
+            max_optimization_time: Duration::from_millis(200),
+            ..short_conf(1)
+        };
+        Ges::new(&data).ges(&mut sol, conf);
+
+        assert_eq!(sol.routes_number(), 2);
+        assert!(sol.validate().is_empty());
+    }
+
+    #[derive(Default)]
+    struct Counts {
+        removed: usize,
+        best: usize,
+        iterations: usize,
+        ejections: usize,
+        perturbations: usize,
+    }
+
+    struct Counter(Arc<std::sync::Mutex<Counts>>);
+
+    impl GesObserver for Counter {
+        fn on_route_removed(&mut self, _: &Sol, _: &Stats) {
+            self.0.lock().unwrap().removed += 1;
+        }
+
+        fn on_new_best(&mut self, _: &Sol, _: &Stats) {
+            self.0.lock().unwrap().best += 1;
+        }
+
+        fn on_iteration(&mut self, _: &Sol, _: &Stats) {
+            self.0.lock().unwrap().iterations += 1;
+        }
+
+        fn on_ejection(&mut self, _: usize, removed: &[usize]) {
+            assert!(!removed.is_empty());
+            self.0.lock().unwrap().ejections += 1;
+        }
+
+        fn on_perturbation(&mut self, _: usize, _: &Sol) {
+            self.0.lock().unwrap().perturbations += 1;
+        }
+    }
+
+    #[test]
+    fn observers_see_every_event() {
+        let data = instance(20);
+        let mut sol = Sol::new(&data);
+        sol.initialize();
+
+        let counts = Arc::new(std::sync::Mutex::new(Counts::default()));
+        let mut ges = Ges::new(&data);
+        ges.add_observer(Box::new(Counter(counts.clone())));
+        let conf = Conf {
+            seed: Some(1),
+            ..short_conf(5)
+        };
+        ges.ges(&mut sol, conf);
+
+        let counts = counts.lock().unwrap();
+        assert_eq!(counts.removed, 15);
+        assert_eq!(counts.best, 15);
+        assert_eq!(
+            counts.iterations,
+            ges.stats().iterations().total() - counts.removed
+        );
+        assert!(counts.ejections + counts.perturbations <= counts.iterations);
+    }
+
+    #[test]
+    fn interrupted_before_start() {
+        let data = instance(20);
+        let mut sol = Sol::new(&data);
+        sol.initialize();
+
+        let mut ges = Ges::new(&data);
+        ges.interrupt_on(Arc::new(AtomicBool::new(true)));
+        ges.ges(&mut sol, short_conf(1));
+
+        assert_eq!(sol.routes_number(), 20);
+    }
+
+    #[test]
+    fn distance_minimization_keeps_routes() {
+        let data = instance(20);
+        let mut sol = Sol::new(&data);
+        sol.initialize();
+
+        let mut ges = Ges::new(&data);
+        ges.ges(&mut sol, short_conf(5));
+
+        let routes = sol.routes_number();
+        let distance = sol.distance();
+
+        ges.minimize_distance(&mut sol, &short_conf(5));
+
+        assert_eq!(sol.routes_number(), routes);
+        assert!(sol.distance() <= distance);
+        assert_eq!(
+            sol.rs().iter().map(|r| r.len()).sum::<usize>(),
+            data.points - 1
+        );
+    }
+
+    #[test]
+    fn routes_stay_within_max_duration() {
+        fn search(data: &Data) -> Sol<'_> {
+            let mut sol = Sol::new(data);
+            sol.initialize();
+            let conf = Conf {
+                max_route_iterations: Some(500),
+                seed: Some(6),
+                ..short_conf(2)
+            };
+            Ges::new(data).ges(&mut sol, conf);
+            sol
+        }
+        let longest = |sol: &Sol| {
+            sol.cost()
+                .routes
+                .iter()
+                .map(|r| r.duration)
+                .fold(0.0, f64::max)
+        };
+
+        let mut data = instance(20);
+        let unlimited = search(&data).snapshot().routes;
+        let max = {
+            let mut sol = Sol::new(&data);
+            sol.initialize();
+            longest(&sol).ceil() as u64 + 50
+        };
+        data.set_max_duration(max);
+        let too_long = sol::validate::check_routes(&data, &unlimited);
+        assert!(too_long
+            .iter()
+            .all(|v| matches!(v, sol::validate::Violation::Duration { .. })));
+        assert!(!too_long.is_empty());
+
+        let limited = search(&data);
+        assert!(limited.routes_number() < 20);
+        assert!(limited.validate().is_empty());
+        assert!(longest(&limited) <= max as f64);
+    }
+
+    #[test]
+    fn stops_when_every_route_is_at_its_max_duration() {
+        // far apart on a circle around the depot, no two requests fit into one route
+        let node = |angle: f64| Node {
+            x: 50.0 * angle.cos(),
+            y: 50.0 * angle.sin(),
+            dem: 0,
+            start: 0,
+            due: 1000,
+            service: 1,
+        };
+        let mut builder = DataBuilder::new(50);
+        builder.depot(Node {
+            x: 0.0,
+            y: 0.0,
+            ..node(0.0)
+        });
+        for i in 0..10 {
+            let angle = i as f64 * std::f64::consts::PI / 5.0;
+            builder.request(node(angle), node(angle));
+        }
+        builder.max_duration(105);
+        let data = builder.build().unwrap();
+
+        let mut sol = Sol::new(&data);
+        sol.initialize();
+        let conf = Conf {
+            max_optimization_time: Duration::from_millis(200),
+            ..short_conf(1)
+        };
+        Ges::new(&data).ges(&mut sol, conf);
+
+        assert_eq!(sol.routes_number(), 10);
+        assert!(sol.validate().is_empty());
+    }
+
+    #[test]
+    fn resumes_where_the_checkpoint_was_taken() {
+        let data = instance(20);
+        let path = std::env::temp_dir().join(format!("ges-checkpoint-{}", std::process::id()));
+        let conf = |max_iterations, checkpoint| Conf {
+            max_optimization_time: Duration::new(60, 0),
+            max_iterations: Some(max_iterations),
+            checkpoint,
+            checkpoint_every: Duration::ZERO,
+            ..short_conf(2)
+        };
+
+        let run = |conf: Conf, checkpoint: Option<&Checkpoint>| {
+            let mut sol = Sol::new(&data);
+            sol.initialize();
+            let mut ges = Ges::new(&data);
+            ges.seed(&mut sol, 5);
+            if let Some(checkpoint) = checkpoint {
+                ges.resume(&mut sol, checkpoint).unwrap();
+            }
+            ges.ges(&mut sol, conf);
+            (sol.snapshot(), ges.stats().iterations().total())
+        };
+
+        let uninterrupted = run(conf(400, None), None);
+
+        run(conf(50, Some(path.clone())), None);
+        let checkpoint = Checkpoint::read(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert!(!checkpoint.solution.ejected.is_empty());
+        assert_eq!(checkpoint.counters.total, 50);
+
+        assert_eq!(run(conf(400, None), Some(&checkpoint)), uninterrupted);
+    }
 }
@@ -1,16 +1,29 @@
+use std::fs::File;
+use std::io::{BufWriter, Write};
 use std::path::Path;
 use std::time::Duration;
 
-use ges::data::Data;
-use ges::routes::ROUTES;
-use ges::Ges;
-use clap::Parser;
+use clap::{Parser, Subcommand};
+use ges::batch::{self, BatchConf};
+use ges::bks::{self, BestKnownTable};
+use ges::bounds::Bounds;
+use ges::checkpoint::Checkpoint;
+use ges::data::{Data, DistanceConvention};
+use ges::jsonl::{Event, JsonlObserver};
+use ges::observer::GesObserver;
+use ges::sol::sintef::{self, SolutionHeader};
+use ges::sol::validate::check_routes;
+use ges::{Ges, LogFormat};
 
 #[derive(Parser, Debug)]
 #[command(author, version, about, long_about = None)]
+#[command(args_conflicts_with_subcommands = true, subcommand_negates_reqs = true)]
 struct Args {
-    #[arg(short, long)]
-    path: String,
+    #[command(subcommand)]
+    command: Option<Command>,
+
+    #[arg(short, long, required = true)]
+    path: Option<String>,
 
     #[arg(short, long)]
     max_time: Option<u64>,
@@ -26,38 +39,360 @@ struct Args {
 
     #[arg(short, long)]
     extra: bool,
+
+    /// ceil, one-decimal, integer or exact
+    #[arg(short, long, default_value = "ceil")]
+    distance: DistanceConvention,
+
+    #[command(flatten)]
+    instance_args: InstanceArgs,
+
+    /// seed for all random choices, making the run reproducible
+    #[arg(short, long)]
+    seed: Option<u64>,
+
+    /// start from the solution in this SINTEF-style file
+    #[arg(short, long)]
+    initial: Option<String>,
+
+    /// write the final solution to this file in the SINTEF format
+    #[arg(short, long)]
+    output: Option<String>,
+
+    #[arg(long, default_value = "")]
+    authors: String,
+
+    #[arg(long, default_value = "")]
+    reference: String,
+
+    /// defaults to today
+    #[arg(long)]
+    date: Option<String>,
+
+    /// run this many independent searches in parallel
+    #[arg(long, default_value_t = 1)]
+    threads: usize,
+
+    /// restart a parallel search from the best solution once it has this many routes more
+    #[arg(long)]
+    restart_lag: Option<usize>,
+
+    /// check insertions into the routes on this many threads
+    #[arg(long, default_value_t = 1)]
+    insertion_threads: usize,
+
+    /// stop after this many iterations in total
+    #[arg(long)]
+    max_iterations: Option<usize>,
+
+    /// give up a route removal, and so end the search, after this many iterations
+    #[arg(long)]
+    max_route_iterations: Option<usize>,
+
+    /// give up a route removal, and so end the search, after this many iterations without fewer
+    /// ejected pairs
+    #[arg(long)]
+    stagnation_limit: Option<usize>,
+
+    /// text or jsonl, printing one JSON object per event
+    #[arg(long, default_value = "text")]
+    log_format: LogFormat,
+
+    /// save the state of the search to this file every --checkpoint-every seconds
+    #[arg(long)]
+    checkpoint: Option<String>,
+
+    #[arg(long, default_value_t = 300)]
+    checkpoint_every: u64,
+
+    /// continue the search saved to this checkpoint; the time and iteration limits count the
+    /// search before it as well
+    #[arg(long, conflicts_with_all = ["initial", "seed"])]
+    resume: Option<String>,
+}
+
+#[derive(Subcommand, Debug)]
+enum Command {
+    /// Solve every instance of a directory, or matching a glob pattern
+    Batch(BatchArgs),
+    /// Check that a solution serves every customer of an instance feasibly
+    Verify(VerifyArgs),
+}
+
+#[derive(clap::Args, Debug)]
+struct VerifyArgs {
+    instance: String,
+
+    /// solution in the SINTEF format
+    solution: String,
+
+    /// ceil, one-decimal, integer or exact
+    #[arg(short, long, default_value = "ceil")]
+    distance: DistanceConvention,
+
+    /// longest time a route may take, overriding the limit of the instance
+    #[arg(long)]
+    max_duration: Option<u64>,
+}
+
+#[derive(clap::Args, Debug)]
+struct BatchArgs {
+    /// directory or glob pattern, e.g. "instances/lc1*.txt"
+    instances: String,
+
+    /// time limit of every run in seconds
+    #[arg(short, long, default_value_t = 60)]
+    max_time: u64,
+
+    /// solve this many instances at the same time
+    #[arg(short, long, default_value_t = 1)]
+    jobs: usize,
+
+    /// run every instance this many times
+    #[arg(short, long, default_value_t = 1)]
+    repetitions: usize,
+
+    /// seed of the first run of every instance, the next runs use the following seeds
+    #[arg(short, long)]
+    seed: Option<u64>,
+
+    /// ceil, one-decimal, integer or exact
+    #[arg(short, long, default_value = "ceil")]
+    distance: DistanceConvention,
+
+    #[command(flatten)]
+    instance_args: InstanceArgs,
+
+    /// write the summary to this file, as JSON if it ends with .json and as CSV otherwise;
+    /// defaults to CSV on stdout
+    #[arg(short, long)]
+    output: Option<String>,
+}
+
+/// What the search is held to and compared with besides the instance file.
+#[derive(clap::Args, Debug)]
+struct InstanceArgs {
+    /// longest time a route may take, overriding the limit of the instance
+    #[arg(long)]
+    max_duration: Option<u64>,
+
+    /// CSV of best-known solutions (instance,routes,distance,reference) to compare with; the
+    /// built-in table has route counts only, so gaps in distance need this file
+    #[arg(long)]
+    bks: Option<String>,
+
+    /// CSV of target numbers of routes (instance,routes), taking precedence over best-known ones
+    #[arg(long)]
+    targets: Option<String>,
+}
+
+fn read_table(path: Option<&str>) -> BestKnownTable {
+    match path.map(|path| (path, BestKnownTable::read(Path::new(path)))) {
+        Some((_, Ok(table))) => table,
+        Some((path, Err(e))) => {
+            eprintln!("{path}: {e}");
+            std::process::exit(1);
+        }
+        None => BestKnownTable::default(),
+    }
+}
+
+/// The built-in best-known route counts, completed and overridden by the file if given.
+fn best_known(bks: Option<&str>) -> BestKnownTable {
+    let mut table = BestKnownTable::builtin();
+    table.extend(read_table(bks));
+    table
 }
 
 fn main() {
     let args = Args::parse();
 
-    let path = Path::new(&args.path);
+    match args.command {
+        Some(Command::Batch(batch)) => run_batch(batch),
+        Some(Command::Verify(verify)) => run_verify(verify),
+        None => solve(args),
+    }
+}
+
+fn run_batch(args: BatchArgs) {
+    let paths = match batch::instances(&args.instances) {
+        Ok(paths) => paths,
+        Err(e) => {
+            eprintln!("{}: {e}", args.instances);
+            std::process::exit(1);
+        }
+    };
+
+    let conf = BatchConf {
+        jobs: args.jobs,
+        repetitions: args.repetitions,
+        convention: args.distance,
+        max_duration: args.instance_args.max_duration,
+        conf: ges::Conf {
+            max_optimization_time: Duration::new(args.max_time, 0),
+            seed: args.seed,
+            ..Default::default()
+        },
+        best_known: best_known(args.instance_args.bks.as_deref()),
+        targets: read_table(args.instance_args.targets.as_deref()),
+    };
+
+    let summaries = batch::run(&paths, &conf, |s| {
+        if let Some(e) = &s.error {
+            eprintln!("{} #{}: {e}", s.instance, s.repetition);
+        } else {
+            eprintln!(
+                "{} #{}: routes {} (target {}), distance {:.4}, {:.3}s{}",
+                s.instance,
+                s.repetition,
+                s.routes,
+                s.target_routes.map_or("-".to_owned(), |t| t.to_string()),
+                s.distance,
+                s.time,
+                match (s.route_gap, s.distance_gap) {
+                    (Some(r), Some(d)) => format!(", gap: routes {r:+}, distance {d:+.2}%"),
+                    (Some(r), None) => format!(", gap: routes {r:+}"),
+                    _ => String::new(),
+                }
+            );
+        }
+    });
+
+    let written = match &args.output {
+        Some(output) => File::create(output).and_then(|f| {
+            let mut w = BufWriter::new(f);
+            if output.ends_with(".json") {
+                batch::write_json(&mut w, &summaries)?;
+            } else {
+                batch::write_csv(&mut w, &summaries)?;
+            }
+            w.flush()
+        }),
+        None => batch::write_csv(&mut std::io::stdout(), &summaries),
+    };
+
+    if let Err(e) = written {
+        eprintln!("{}: {e}", args.output.unwrap_or_default());
+        std::process::exit(1);
+    }
+}
+
+fn run_verify(args: VerifyArgs) {
+    let mut data = match Data::read_with(Path::new(&args.instance), args.distance) {
+        Ok(data) => data,
+        Err(e) => {
+            eprintln!("{}: {e}", args.instance);
+            std::process::exit(1);
+        }
+    };
+    if let Some(duration) = args.max_duration {
+        data.set_max_duration(duration);
+    }
+
+    let routes = match sintef::read_routes(Path::new(&args.solution)) {
+        Ok(routes) => routes,
+        Err(e) => {
+            eprintln!("{}: {e}", args.solution);
+            std::process::exit(1);
+        }
+    };
+
+    let violations = check_routes(&data, &routes);
+    if !violations.is_empty() {
+        for v in violations.iter() {
+            println!("{v}");
+        }
+        eprintln!("{}: {} violations", args.solution, violations.len());
+        std::process::exit(1);
+    }
+
+    let mut solution = ges::sol::Sol::new(&data);
+    solution.initialize_from(&routes).unwrap();
+    println!("{}", solution.cost());
+}
+
+fn solve(args: Args) {
+    let path = Path::new(args.path.as_deref().unwrap());
     let instance = path
         .file_stem()
         .unwrap()
         .to_ascii_lowercase()
         .into_string()
         .unwrap();
-    let data = Data::read(path);
+    let mut data = match Data::read_with(path, args.distance) {
+        Ok(data) => data,
+        Err(e) => {
+            eprintln!("{}: {e}", path.display());
+            std::process::exit(1);
+        }
+    };
+    if let Some(duration) = args.instance_args.max_duration {
+        data.set_max_duration(duration);
+    }
+
+    let best_known = best_known(args.instance_args.bks.as_deref());
+    let targets = read_table(args.instance_args.targets.as_deref());
 
     let mut solution = ges::sol::Sol::new(&data);
-    solution.initialize();
+    if let Some(initial) = &args.initial {
+        let loaded = sintef::read_routes(Path::new(initial))
+            .and_then(|routes| solution.initialize_from(&routes));
+
+        if let Err(e) = loaded {
+            eprintln!("{initial}: {e}");
+            std::process::exit(1);
+        }
+    } else {
+        solution.initialize();
+    }
     let mut ges = Ges::new(&data);
 
+    if args.threads > 1 && (args.checkpoint.is_some() || args.resume.is_some()) {
+        eprintln!("checkpoints are not supported with more than one thread");
+        std::process::exit(1);
+    }
+
+    if let Some(resume) = &args.resume {
+        let resumed = Checkpoint::read(Path::new(resume))
+            .and_then(|checkpoint| ges.resume(&mut solution, &checkpoint));
+
+        if let Err(e) = resumed {
+            eprintln!("{resume}: {e}");
+            std::process::exit(1);
+        }
+    }
+
     let mut conf = ges::Conf::default();
 
-    conf.target_routes = if let Some(x) = args.target_routes {
-        x
-    } else {
-        ROUTES[&instance]
-    };
+    let target = bks::target_routes(&instance, args.target_routes, &targets, &best_known);
 
-    conf.max_optimization_time = if let Some(x) = args.max_time {
-        Duration::new(x, 0)
-    } else {
-        Duration::new(u64::max_value(), 0)
+    let bounds = Bounds::new(&data);
+    conf.target_routes = target.unwrap_or(bounds.routes());
+
+    conf.max_optimization_time = match (args.max_time, target) {
+        (Some(x), _) => Duration::new(x, 0),
+        (None, Some(_)) => Duration::new(u64::max_value(), 0),
+        // without a target only the time limit ends the search
+        (None, None) => conf.max_optimization_time,
     };
 
+    if target.is_none() {
+        eprintln!(
+            "{instance}: no target number of routes, aiming at the {bounds} for {:?}",
+            conf.max_optimization_time
+        );
+    }
+
+    conf.seed = args.seed;
+    conf.threads = args.threads;
+    conf.restart_lag = args.restart_lag;
+    conf.insertion_threads = args.insertion_threads;
+    conf.max_iterations = args.max_iterations;
+    conf.max_route_iterations = args.max_route_iterations;
+    conf.stagnation_limit = args.stagnation_limit;
+    conf.checkpoint = args.checkpoint.map(Into::into);
+    conf.checkpoint_every = Duration::new(args.checkpoint_every, 0);
+
     conf.log = if args.quiet {
         ges::Log::Quiet
     } else if args.verbose {
@@ -68,12 +403,92 @@ fn main() {
         ges::Log::Quiet
     };
 
-    print!(
-        "{:?} {} {:?} ",
-        instance,
-        conf.target_routes,
-        conf.max_optimization_time
-    );
+    conf.log_format = args.log_format;
+    let jsonl = conf.log_format == LogFormat::Jsonl;
+
+    if jsonl {
+        ges.add_observer(Box::new(JsonlObserver::new(std::io::stdout())));
+        log_event(&Event::Start {
+            instance: instance.clone(),
+            points: data.points,
+            target_routes: conf.target_routes,
+            lower_bound: bounds.routes(),
+            max_time: conf.max_optimization_time.as_secs_f64(),
+            seed: conf.seed,
+        });
+    } else {
+        print!(
+            "{:?} {} {:?} ",
+            instance, conf.target_routes, conf.max_optimization_time
+        );
+    }
+
+    let ges = if conf.threads > 1 {
+        ges::portfolio::solve(&data, &mut solution, &conf, |search| {
+            let observers: Vec<Box<dyn GesObserver + Send>> = if jsonl {
+                vec![Box::new(
+                    JsonlObserver::new(std::io::stdout()).search(search),
+                )]
+            } else {
+                vec![]
+            };
+            observers
+        })
+    } else {
+        ges.ges(&mut solution, conf);
+        ges
+    };
+
+    let cost = solution.cost();
+    if jsonl {
+        log_event(&Event::result(&solution, &cost, ges.stats()));
+    } else {
+        println!("{}", cost);
+        println!("{bounds}");
+    }
+
+    if let Some(best) = best_known.get(&instance) {
+        let gap = best.gap(solution.routes_number(), cost.distance);
+        if jsonl {
+            log_event(&Event::BestKnown {
+                best: best.clone(),
+                gap,
+            });
+        } else {
+            let distance = best.distance.map_or("-".to_owned(), |d| d.to_string());
+            println!(
+                "best known: routes: {}, distance: {distance}, gap: {gap}",
+                best.routes
+            );
+        }
+    }
+
+    if let Some(output) = args.output {
+        let header = SolutionHeader {
+            instance: path.file_stem().unwrap().to_string_lossy().into_owned(),
+            authors: args.authors,
+            reference: args.reference,
+            date: args.date.unwrap_or_else(sintef::today),
+        };
+
+        let written = File::create(&output).and_then(|f| {
+            let mut w = BufWriter::new(f);
+            solution.write_solution(&mut w, &header)?;
+            w.flush()
+        });
+
+        if let Err(e) = written {
+            eprintln!("{output}: {e}");
+            std::process::exit(1);
+        }
+    }
+}
 
-    ges.ges(&mut solution, conf);
+/// Writes an event of the run log, quietly if stdout was closed early.
+fn log_event(event: &Event) {
+    if let Err(e) = event.write(&mut std::io::stdout()) {
+        if e.kind() != std::io::ErrorKind::BrokenPipe {
+            eprintln!("cannot write the run log: {e}");
+        }
+    }
 }
@@ -26,18 +26,23 @@ impl Move {
         }
     }
 
-    pub fn maybe_switch(&mut self, put_pickup_between: &Between, put_delivery_between: &Between) {
+    pub fn maybe_switch<R: Rng>(
+        &mut self,
+        put_pickup_between: &Between,
+        put_delivery_between: &Between,
+        rng: &mut R,
+    ) {
         self.times += 1;
-        let r = rand::thread_rng().gen_range(1..=self.times);
+        let r = rng.gen_range(1..=self.times);
         if r == 1 {
             self.put_pickup_between = *put_pickup_between;
             self.put_delivery_between = *put_delivery_between;
         }
     }
 
-    pub fn pick(&mut self, other: &Self) {
+    pub fn pick<R: Rng>(&mut self, other: &Self, rng: &mut R) {
         self.times += other.times;
-        let r = rand::thread_rng().gen_range(1..=self.times);
+        let r = rng.gen_range(1..=self.times);
         if r <= other.times {
             self.put_pickup_between = other.put_pickup_between;
             self.put_delivery_between = other.put_delivery_between;
@@ -45,9 +50,9 @@ impl Move {
         }
     }
 
-    pub fn pick2(self, other: Self) -> Self {
+    pub fn pick2<R: Rng>(self, other: Self, rng: &mut R) -> Self {
         let times = self.times + other.times;
-        let r = rand::thread_rng().gen_range(1..=times);
+        let r = rng.gen_range(1..=times);
         Self {
             times,
             ..(if r <= other.times { other } else { self })
@@ -79,6 +84,10 @@ impl Swap {
 
 #[cfg(test)]
 mod test {
+    use rand::SeedableRng;
+
+    use crate::Prng;
+
     use super::*;
 
     #[test]
@@ -108,15 +117,17 @@ mod test {
             removed: [0; K_MAX],
         };
 
+        let rng = &mut Prng::seed_from_u64(0);
+
         let mut m = Move::new(1);
-        m.maybe_switch(&other.put_pickup_between, &other.put_delivery_between);
+        m.maybe_switch(&other.put_pickup_between, &other.put_delivery_between, rng);
         assert_eq!(m, other);
 
         let mut m = Move::new(1);
-        m.pick(&other);
+        m.pick(&other, rng);
         assert_eq!(m, other);
 
-        let m = Move::new(1).pick2(other);
+        let m = Move::new(1).pick2(other, rng);
         assert_eq!(m, other);
     }
 
@@ -144,10 +155,12 @@ mod test {
         let mut b_res = b.clone();
         b_res.times = 15;
 
-        let m = a.clone().pick2(b);
+        let rng = &mut Prng::seed_from_u64(0);
+
+        let m = a.clone().pick2(b, rng);
         assert!(m == a_res || m == b_res);
 
-        a.pick(&b);
+        a.pick(&b, rng);
         assert!(a == a_res || a == b_res);
     }
 }
@@ -0,0 +1,21 @@
+use crate::sol::Sol;
+use crate::stats::Stats;
+
+/// Receives the progress of the search in `Ges`. Every method does nothing by default, so an
+/// observer implements only the events it cares about.
+pub trait GesObserver {
+    /// A random route was taken out and its pairs ejected.
+    fn on_route_removed(&mut self, _solution: &Sol, _stats: &Stats) {}
+
+    /// All ejected pairs were put back, the solution has one route less than before.
+    fn on_new_best(&mut self, _solution: &Sol, _stats: &Stats) {}
+
+    /// An ejected pair was either inserted or, failing that, the solution perturbed.
+    fn on_iteration(&mut self, _solution: &Sol, _stats: &Stats) {}
+
+    /// The pickup was inserted at the cost of ejecting the `removed` pickups.
+    fn on_ejection(&mut self, _pickup: usize, _removed: &[usize]) {}
+
+    /// The pickup could not be inserted and the solution was perturbed instead.
+    fn on_perturbation(&mut self, _pickup: usize, _solution: &Sol) {}
+}
@@ -0,0 +1,157 @@
+use crate::data::Data;
+use crate::observer::GesObserver;
+use crate::sol::Sol;
+use crate::{Conf, Ges};
+use itertools::Itertools;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread;
+
+/// Runs `conf.threads` independent searches, each with its own seed, starting from `solution`.
+/// Whenever a search removes a route and ends up with fewer routes than the best found so far, it
+/// publishes its solution. With `conf.restart_lag` a search that falls that many routes behind
+/// continues from the best one instead. All searches stop once one of them reaches the target,
+/// and `solution` becomes the best solution found. No checkpoints are written.
+///
+/// Search `i` reports to the observers made by `observers(i)`. The search that found the best
+/// solution also shortens its routes and is returned, e.g. for its statistics.
+pub fn solve<'a, F>(data: &'a Data, solution: &mut Sol<'a>, conf: &Conf, observers: F) -> Ges<'a>
+where
+    F: Fn(usize) -> Vec<Box<dyn GesObserver + Send + 'a>> + Sync,
+{
+    let base = conf.seed.unwrap_or_else(rand::random);
+    let threads = conf.threads.max(1);
+    // the searches run side by side, there is no single state to continue from
+    let conf = &Conf {
+        checkpoint: None,
+        ..conf.clone()
+    };
+
+    // the best solution and the search that found it
+    let best = Mutex::new((solution.snapshot(), 0));
+    let stop = Arc::new(AtomicBool::new(false));
+    let observers = &observers;
+
+    let mut searches = thread::scope(|s| {
+        let handles = (0..threads)
+            .map(|i| {
+                let mut sol = solution.clone();
+                let best = &best;
+                let stop = stop.clone();
+
+                s.spawn(move || {
+                    let mut ges = Ges::new(data);
+                    for observer in observers(i) {
+                        ges.add_observer(observer);
+                    }
+                    ges.seed(&mut sol, base.wrapping_add(i as u64));
+                    ges.interrupt_on(stop.clone());
+
+                    loop {
+                        if conf.reached_target(sol.routes_number()) || ges.out_of_budget(conf) {
+                            break;
+                        }
+
+                        if !ges.remove_route(&mut sol, conf) {
+                            break;
+                        }
+
+                        let mut best = best.lock().unwrap();
+                        if sol.routes_number() < best.0.routes.len() {
+                            *best = (sol.snapshot(), i);
+                            if conf.prints_progress() {
+                                print!("thread {i}, ");
+                                ges.stats().print_after_route_removal(&sol);
+                            }
+                        } else if let Some(lag) = conf.restart_lag {
+                            if sol.routes_number() >= best.0.routes.len() + lag {
+                                // our own random choices are kept, otherwise we would repeat the
+                                // search of the thread that found the best solution
+                                sol.restore_unchecked(&best.0);
+                            }
+                        }
+                    }
+
+                    if conf.reached_target(sol.routes_number()) && sol.heap.size == 0 {
+                        stop.store(true, Ordering::Relaxed);
+                    }
+
+                    ges
+                })
+            })
+            .collect_vec();
+
+        handles
+            .into_iter()
+            .map(|handle| handle.join().unwrap())
+            .collect_vec()
+    });
+
+    let (best, winner) = best.into_inner().unwrap();
+    let mut ges = searches.swap_remove(winner);
+    solution.restore_unchecked(&best);
+    if conf.prints_summary() {
+        ges.stats().print_after_route_removal(solution);
+    }
+    // the flag only stopped the other searches, the routes are still to be shortened
+    ges.interrupt = None;
+    ges.finish(solution, conf);
+    ges
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::evaluator::Evaluator;
+    use crate::tests::{instance, short_conf};
+    use std::time::Duration;
+
+    fn assert_send<T: Send>() {}
+
+    #[test]
+    fn searches_can_move_between_threads() {
+        assert_send::<Sol>();
+        assert_send::<Evaluator>();
+    }
+
+    #[test]
+    fn one_search_follows_its_seed() {
+        let data = instance(20);
+        let conf = Conf {
+            seed: Some(3),
+            ..short_conf(5)
+        };
+
+        let mut sol = Sol::new(&data);
+        sol.initialize();
+        solve(&data, &mut sol, &conf, |_| Vec::new());
+        assert_eq!(sol.routes_number(), 5);
+        assert_eq!(sol.heap.size, 0);
+        assert!(sol.check_routes());
+
+        let mut alone = Sol::new(&data);
+        alone.initialize();
+        Ges::new(&data).ges(&mut alone, conf);
+        assert_eq!(sol.rs(), alone.rs());
+    }
+
+    #[test]
+    fn searches_side_by_side() {
+        let data = instance(20);
+        let mut sol = Sol::new(&data);
+        sol.initialize();
+
+        let conf = Conf {
+            max_optimization_time: Duration::new(1, 0),
+            threads: 3,
+            restart_lag: Some(2),
+            seed: Some(3),
+            ..short_conf(5)
+        };
+        solve(&data, &mut sol, &conf, |_| Vec::new());
+
+        assert!(sol.routes_number() < 20);
+        assert_eq!(sol.heap.size, 0);
+        assert!(sol.validate().is_empty());
+    }
+}
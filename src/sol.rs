@@ -1,43 +1,64 @@
-use std::{collections::HashSet, mem::replace};
+use std::collections::BTreeSet;
 
 use itertools::Itertools;
 use rand::seq::IteratorRandom;
-use rand::Rng;
+use rand::{Rng, SeedableRng};
 
-use crate::data::{Data, PTS};
+use crate::data::Data;
 use crate::eval::Eval;
 use crate::evaluator::Evaluator;
 use crate::mov::{Between, Move, Swap};
-use crate::{K_MAX, UNSERVED};
+use crate::{Prng, K_MAX, UNSERVED};
 
 use self::heap::Heap;
+use self::journal::{Change, Journal};
 
+pub mod cost;
 mod heap;
+mod journal;
+pub mod sintef;
+pub mod snapshot;
+pub mod validate;
 
+#[derive(Clone)]
 pub struct Sol<'a> {
     pub data: &'a Data,
-    pub next: [usize; PTS],
-    pub prev: [usize; PTS],
-    pub latest_feasible_departure: [u64; PTS],
-    pub first: [usize; PTS],
-    pub routes: HashSet<usize>,
+    pub next: Vec<usize>,
+    pub prev: Vec<usize>,
+    pub latest_feasible_departure: Vec<u64>,
+    /// travel and service time from arriving at a node to being back at the depot, without
+    /// waiting, kept only with a maximum duration
+    pub remaining_time: Vec<u64>,
+    /// the earliest the vehicle can be back at the depot after a node, however early it gets
+    /// there, kept only with a maximum duration
+    pub earliest_return: Vec<u64>,
+    pub first: Vec<usize>,
+    pub routes: BTreeSet<usize>,
     pub heap: Heap,
+    pub rng: Prng,
+    journal: Option<Journal>,
 }
 
 impl<'a> Sol<'a> {
     pub fn new(data: &'a Data) -> Self {
-        let unserved = [UNSERVED; PTS];
-        let mut latest_feasible_departure = [0; PTS];
+        let unserved = vec![UNSERVED; data.points];
+        let mut latest_feasible_departure = vec![0; data.points];
         latest_feasible_departure[0] = data.pts[0].due;
+        let mut earliest_return = vec![0; data.points];
+        earliest_return[0] = data.pts[0].start;
 
         Sol {
             data,
             next: unserved.clone(),
             prev: unserved.clone(),
             latest_feasible_departure,
-            heap: Heap::new(),
-            first: unserved.clone(),
-            routes: HashSet::new(),
+            remaining_time: vec![0; data.points],
+            earliest_return,
+            heap: Heap::new(data.points),
+            first: unserved,
+            routes: BTreeSet::new(),
+            rng: Prng::from_entropy(),
+            journal: None,
         }
     }
 
@@ -51,26 +72,30 @@ impl<'a> Sol<'a> {
         }
     }
 
-    pub fn random_route_first(&self) -> usize {
-        *self
-            .routes
-            .iter()
-            .sorted()
-            .choose(&mut rand::thread_rng())
-            .unwrap()
+    pub fn random_route_first(&mut self) -> usize {
+        *self.routes.iter().choose(&mut self.rng).unwrap()
     }
 
     fn fix_latest_feasible_departures(&mut self, last: usize) {
         let mut node = last;
         let pts = &self.data.pts;
+        let limited = self.data.limits_duration();
         let mut after_node = self.next[node];
         let mut latest_feasible_departure = self.latest_feasible_departure[after_node];
 
         while node != 0 {
+            // saturating, as a due time shorter than the travel time to it leaves no time to depart
             latest_feasible_departure =
-                latest_feasible_departure - self.data.time_between(node, after_node);
+                latest_feasible_departure.saturating_sub(self.data.time_between(node, after_node));
             latest_feasible_departure = pts[node].due.min(latest_feasible_departure);
-            self.latest_feasible_departure[node] = latest_feasible_departure;
+            self.set_latest_feasible_departure(node, latest_feasible_departure);
+            if limited {
+                let remaining_time =
+                    self.remaining_time[after_node] + self.data.time_between(node, after_node);
+                let earliest_return =
+                    (pts[node].start + remaining_time).max(self.earliest_return[after_node]);
+                self.set_return(node, remaining_time, earliest_return);
+            }
             after_node = node;
             node = self.prev[node];
         }
@@ -82,23 +107,24 @@ impl<'a> Sol<'a> {
         let first_non_depot = route[1];
         let last_non_depot = route[route.len() - 2];
         for (&before, &after) in route.iter().tuple_windows() {
-            self.prev[after] = before;
-            self.next[before] = after;
-            self.first[before] = first_non_depot;
+            self.set_prev(after, before);
+            self.set_next(before, after);
+            self.set_first(before, first_non_depot);
         }
-        self.prev[0] = 0;
-        self.next[0] = 0;
+        self.set_prev(0, 0);
+        self.set_next(0, 0);
 
         self.fix_latest_feasible_departures(last_non_depot);
 
-        self.routes.insert(first_non_depot);
+        self.insert_route(first_non_depot);
 
         debug_assert!(self.check_if_route_was_correctly_calcualted(route));
     }
 
     fn check_if_route_was_correctly_calcualted(&mut self, route: &Vec<usize>) -> bool {
+        // the eval already starts at the depot, stepping to it again would add its service time
         let mut e = Eval::new();
-        route.iter().fold(true, |acc, &n| {
+        route[1..].iter().fold(true, |acc, &n| {
             e.next(n, self.data);
             acc && e.time <= self.latest_feasible_departure[n]
         })
@@ -109,13 +135,24 @@ impl<'a> Sol<'a> {
     }
 
     pub fn routes_number(&self) -> usize {
-        self.routes.iter().count()
+        self.routes.len()
     }
 
+    /// Moves or swaps random requests. Only routes of several requests give any away, and a swap
+    /// needs two of them; without those the solution is left as it is.
     pub fn perturb(&mut self, ev: &mut Evaluator) {
-        if rand::thread_rng().gen_bool(0.5) {
-            self.random_move(ev);
-        } else {
+        let shared = self
+            .routes
+            .iter()
+            .filter(|&&first| !self.only_pickup_in_route(first))
+            .take(2)
+            .count();
+
+        if self.rng.gen_bool(0.5) {
+            if shared > 0 {
+                self.random_move(ev);
+            }
+        } else if shared > 1 {
             self.random_swap(ev);
         }
     }
@@ -169,27 +206,22 @@ impl<'a> Sol<'a> {
     }
 
     fn random_idx(&mut self) -> usize {
-        rand::thread_rng().gen_range(1..self.data.points)
+        self.rng.gen_range(1..self.data.points)
     }
 
     pub fn try_insert_1(&self, pickup: usize, ev: &mut Evaluator) -> Option<Move> {
         ev.reset(pickup);
 
-        self.routes
-            .iter()
-            .filter_map(|&route| ev.check_add_to_route(&self, route))
-            .reduce(Move::pick2)
+        ev.pick_over_routes(&self.routes, |ev, route| ev.check_add_to_route(self, route))
     }
 
     pub fn try_insert_k(&self, pickup: usize, ev: &mut Evaluator) -> Option<Move> {
         ev.reset(pickup);
 
         for k in 1..=K_MAX {
-            let mov = self
-                .routes
-                .iter()
-                .filter_map(|&route| ev.check_add_to_route_with_k_removed(&self, route, k))
-                .reduce(Move::pick2);
+            let mov = ev.pick_over_routes(&self.routes, |ev, route| {
+                ev.check_add_to_route_with_k_removed(self, route, k)
+            });
 
             if mov.is_some() {
                 return mov;
@@ -204,6 +236,48 @@ impl<'a> Sol<'a> {
         self.try_insert_1(pickup, ev).or_else(|| self.try_insert_k(pickup, ev))
     }
 
+    pub fn try_insert_cheapest(&self, pickup: usize, ev: &mut Evaluator) -> Option<(i64, Move)> {
+        ev.reset(pickup);
+
+        self.routes
+            .iter()
+            .filter_map(|&route| ev.check_cheapest_insertion(self, route))
+            .min_by_key(|&(delta, _)| delta)
+    }
+
+    /// Decrease in distance caused by taking the pickup and its delivery out of their route.
+    pub fn removal_gain(&self, pickup: usize) -> i64 {
+        let delivery = self.data.pair_of(pickup);
+        let dist = |a: usize, b: usize| self.data.dist_between(a, b) as i64;
+
+        let (before_pickup, after_pickup) = (self.prev[pickup], self.next[pickup]);
+        let (before_delivery, after_delivery) = (self.prev[delivery], self.next[delivery]);
+
+        if after_pickup == delivery {
+            dist(before_pickup, pickup) + dist(pickup, delivery) + dist(delivery, after_delivery)
+                - dist(before_pickup, after_delivery)
+        } else {
+            dist(before_pickup, pickup) + dist(pickup, after_pickup)
+                - dist(before_pickup, after_pickup)
+                + dist(before_delivery, delivery)
+                + dist(delivery, after_delivery)
+                - dist(before_delivery, after_delivery)
+        }
+    }
+
+    pub fn distance(&self) -> u64 {
+        self.routes
+            .iter()
+            .map(|&first| {
+                let mut e = Eval::new();
+                for node in self.route_iter(first) {
+                    e.next(node, self.data);
+                }
+                e.distance
+            })
+            .sum()
+    }
+
     pub fn only_pickup_in_route(&self, pickup: usize) -> bool {
         let delivery = self.data.pair_of(pickup);
 
@@ -220,22 +294,29 @@ impl<'a> Sol<'a> {
 
     pub fn remove_route(&mut self, first: usize) {
         debug_assert!(self.prev[first] == 0);
-        self.routes.remove(&first);
+        self.forget_route(first);
+        self.save_heap();
         let mut idx = first;
         while idx != 0 {
-            self.first[idx] = UNSERVED;
+            self.set_first(idx, UNSERVED);
             if !self.data.pts[idx].is_delivery {
                 self.heap.push(idx);
-                self.heap.removed_times[idx] += 1;
+                self.set_removed_times(idx, self.heap.removed_times[idx] + 1);
             }
-            self.prev[idx] = UNSERVED;
-            idx = replace(&mut self.next[idx], UNSERVED);
+            self.set_prev(idx, UNSERVED);
+            let after = self.next[idx];
+            self.set_next(idx, UNSERVED);
+            idx = after;
         }
 
         self.heap.sort()
     }
 
     pub fn inc(&mut self) {
+        self.save_heap();
+        if let Some(top) = self.heap.top() {
+            self.record(Change::RemovedTimes(top, self.heap.removed_times[top]));
+        }
         self.heap.inc()
     }
 
@@ -244,6 +325,7 @@ impl<'a> Sol<'a> {
     }
 
     pub fn push(&mut self, idx: usize) {
+        self.save_heap();
         self.heap.push(idx)
     }
 
@@ -252,6 +334,7 @@ impl<'a> Sol<'a> {
     }
 
     pub fn pop(&mut self) {
+        self.save_heap();
         self.heap.pop()
     }
 
@@ -274,7 +357,7 @@ impl<'a> Sol<'a> {
 
         let mut node = first;
         while node != 0 {
-            self.first[node] = first;
+            self.set_first(node, first);
             prev = node;
             node = self.next[node];
         }
@@ -283,14 +366,14 @@ impl<'a> Sol<'a> {
     }
 
     pub fn remove_pair(&mut self, pickup_idx: usize) {
-        self.routes.remove(&pickup_idx);
+        self.forget_route(pickup_idx);
         debug_assert!(pickup_idx != 0);
-        debug_assert!(pickup_idx < PTS);
+        debug_assert!(pickup_idx < self.data.points);
 
         let delivery_idx = self.data.pair_of(pickup_idx);
 
         let first_after_removal = self.get_first_after_removal(pickup_idx, delivery_idx);
-        self.routes.insert(first_after_removal);
+        self.insert_route(first_after_removal);
 
         self.unlink_unsafe(pickup_idx);
         self.unlink_unsafe(delivery_idx);
@@ -305,17 +388,17 @@ impl<'a> Sol<'a> {
         debug_assert!(after != UNSERVED);
 
         // needs to be in for is_removed to work
-        self.next[point_idx] = UNSERVED;
-        self.prev[point_idx] = UNSERVED;
+        self.set_next(point_idx, UNSERVED);
+        self.set_prev(point_idx, UNSERVED);
 
-        self.next[before] = after;
-        self.prev[after] = before;
+        self.set_next(before, after);
+        self.set_prev(after, before);
 
-        self.first[point_idx] = UNSERVED;
+        self.set_first(point_idx, UNSERVED);
 
         // in case before or after is the depot
-        self.next[0] = 0;
-        self.prev[0] = 0;
+        self.set_next(0, 0);
+        self.set_prev(0, 0);
     }
 
     pub fn make_swap(&mut self, swap: &Swap) {
@@ -336,10 +419,10 @@ impl<'a> Sol<'a> {
         self.remove_pair(a_pickup_idx); // TODO: inefficient, fix
         self.remove_pair(b_pickup_idx); // TODO: inefficient, fix
 
-        self.routes.remove(&swap.a.put_pickup_between.0);
-        self.routes.remove(&swap.a.put_pickup_between.1);
-        self.routes.remove(&swap.b.put_pickup_between.0);
-        self.routes.remove(&swap.b.put_pickup_between.1);
+        self.forget_route(swap.a.put_pickup_between.0);
+        self.forget_route(swap.a.put_pickup_between.1);
+        self.forget_route(swap.b.put_pickup_between.0);
+        self.forget_route(swap.b.put_pickup_between.1);
 
         self.link_unsafe(a_pickup_idx, &swap.a.put_pickup_between);
         self.link_unsafe(a_delivery_idx, &swap.a.put_delivery_between);
@@ -347,11 +430,11 @@ impl<'a> Sol<'a> {
         self.link_unsafe(b_delivery_idx, &swap.b.put_delivery_between);
 
         let first = self.first[a_pickup_idx];
-        self.routes.insert(first);
+        self.insert_route(first);
         self.fix_route(first);
 
         let first = self.first[b_pickup_idx];
-        self.routes.insert(first);
+        self.insert_route(first);
         self.fix_route(first);
     }
 
@@ -365,18 +448,18 @@ impl<'a> Sol<'a> {
 
         for &removed in mov.removed.iter().filter(|&&x| x != 0) {
             self.remove_pair(removed); // TODO: inefficient, fix
-            self.heap.push(removed);
-            self.heap.inc();
+            self.push(removed);
+            self.inc();
         }
 
-        self.routes.remove(&mov.put_pickup_between.0);
-        self.routes.remove(&mov.put_pickup_between.1);
+        self.forget_route(mov.put_pickup_between.0);
+        self.forget_route(mov.put_pickup_between.1);
 
         self.link_unsafe(pickup_idx, &mov.put_pickup_between);
         self.link_unsafe(delivery_idx, &mov.put_delivery_between);
 
         let first = self.first[pickup_idx];
-        self.routes.insert(first);
+        self.insert_route(first);
         self.fix_route(first);
     }
 
@@ -392,17 +475,17 @@ impl<'a> Sol<'a> {
             self.first[before]
         };
 
-        self.first[point_idx] = first;
+        self.set_first(point_idx, first);
 
-        self.next[before] = point_idx;
-        self.next[point_idx] = after;
+        self.set_next(before, point_idx);
+        self.set_next(point_idx, after);
 
-        self.prev[after] = point_idx;
-        self.prev[point_idx] = before;
+        self.set_prev(after, point_idx);
+        self.set_prev(point_idx, before);
 
         // in case before or after is the depot
-        self.next[0] = 0;
-        self.prev[0] = 0;
+        self.set_next(0, 0);
+        self.set_prev(0, 0);
     }
 
     pub fn check_routes(&mut self) -> bool {
@@ -427,7 +510,6 @@ impl<'a> Sol<'a> {
             debug_assert!(route.len() % 2 == 0);
 
             debug_assert!(self.next[*route.last().unwrap()] == 0);
-            println!("{route:?}");
         }
 
         for i in 1..self.data.points {
@@ -465,20 +547,8 @@ impl<'a> Sol<'a> {
     }
 
     pub fn eprn(&self) {
-        let rs = self.rs();
-
-        eprintln!("Instance name:");
-        eprintln!("Authors:");
-        eprintln!("Reference:");
-        eprintln!("Date:");
-        eprintln!("Solution :");
-        for (i, r) in rs.iter().sorted().enumerate() {
-            eprint!("Route {} :", i + 1);
-            for x in r {
-                eprint!(" {x}");
-            }
-            eprintln!("");
-        }
+        // nothing sensible can be done when stderr is gone
+        let _ = self.write_solution(&mut std::io::stderr().lock(), &Default::default());
     }
 
     pub fn route_iter(&self, start: usize) -> RouteIterator {
@@ -517,3 +587,160 @@ impl<'a> Iterator for RouteIterator<'a> {
         }
     }
 }
+
+#[cfg(test)]
+mod test {
+    use crate::data::builder::{DataBuilder, Node};
+    use crate::data::MULT;
+
+    use super::*;
+
+    #[test]
+    fn asymmetric_latest_feasible_departures() {
+        let node = |dem, due| Node {
+            dem,
+            due,
+            ..Default::default()
+        };
+
+        let mut builder = DataBuilder::new(10);
+        builder.depot(node(0, 100));
+        builder.request(node(5, 100), node(-5, 50));
+        builder.request(node(5, 100), node(-5, 100));
+        #[rustfmt::skip]
+        builder.travel_times(vec![
+            0.0, 10.0, 1.0, 1.0, 1.0,
+            1.0, 0.0, 5.0, 1.0, 1.0,
+            20.0, 30.0, 0.0, 1.0, 1.0,
+            1.0, 1.0, 1.0, 0.0, 1.0,
+            1.0, 1.0, 1.0, 1.0, 0.0,
+        ]);
+        let data = builder.build().unwrap();
+
+        let mut sol = Sol::new(&data);
+        sol.add_route(&vec![0, 1, 2, 0]);
+
+        // 2 -> 0 takes 20, so 2 has to be left by 80, but its due time is 50;
+        // 1 -> 2 takes 5 (and not 30 as 2 -> 1), so 1 has to be left by 45
+        assert_eq!(sol.latest_feasible_departure[2], 50 * MULT);
+        assert_eq!(sol.latest_feasible_departure[1], 45 * MULT);
+
+        let mut e = Eval::new();
+        for n in [1, 2, 0] {
+            e.next(n, &data);
+            assert!(e.is_feasible(&data));
+        }
+        assert_eq!(e.time, 35 * MULT);
+
+        let mut ev = Evaluator::new(&data);
+        let mov = sol.try_insert_1(3, &mut ev).unwrap();
+        sol.make_move(&mov);
+        assert_eq!(sol.routes_number(), 1);
+        assert!(sol.check_routes());
+    }
+
+    #[test]
+    fn max_duration() {
+        let node = |x, start, due| Node {
+            x,
+            start,
+            due,
+            ..Default::default()
+        };
+
+        let mut builder = DataBuilder::new(10);
+        builder.depot(node(0.0, 0, 1000));
+        let (near, near_delivery) = builder.request(node(10.0, 0, 1000), node(10.0, 0, 1000));
+        let (early, early_delivery) = builder.request(node(10.0, 0, 20), node(10.0, 0, 1000));
+        let (far, far_delivery) = builder.request(node(100.0, 300, 300), node(100.0, 300, 1000));
+        builder.max_duration(250);
+        let data = builder.build().unwrap();
+
+        let mut sol = Sol::new(&data);
+        sol.add_route(&vec![0, far, far_delivery, 0]);
+
+        // served at 300 at the earliest, the far request is 100 away from the depot
+        assert_eq!(sol.remaining_time[far], 100 * MULT);
+        assert_eq!(sol.earliest_return[far], 400 * MULT);
+
+        // the early request has to be served before waiting for the far one, the near one can
+        // be served on the way by leaving later
+        let mut ev = Evaluator::new(&data);
+        assert!(sol.try_insert_1(early, &mut ev).is_none());
+        let mov = sol.try_insert_1(near, &mut ev).unwrap();
+        sol.make_move(&mov);
+        assert_eq!(
+            sol.validate(),
+            [
+                validate::Violation::Unserved(early),
+                validate::Violation::Unserved(early_delivery)
+            ]
+        );
+
+        assert!(
+            validate::check_routes(&data, &[vec![near, far, far_delivery, near_delivery]])
+                .iter()
+                .all(|v| matches!(v, validate::Violation::Unserved(_)))
+        );
+        let too_long = validate::check_routes(
+            &data,
+            &[
+                vec![early, far, far_delivery, early_delivery],
+                vec![near, near_delivery],
+            ],
+        );
+        assert_eq!(
+            too_long,
+            [validate::Violation::Duration {
+                route: 1,
+                duration: 390.0,
+                max: 250.0
+            }]
+        );
+    }
+
+    #[test]
+    fn depot_service_time() {
+        let node = |x, due, service| Node {
+            x,
+            due,
+            service,
+            ..Default::default()
+        };
+
+        // leaving the depot takes 5, so the pickup is reached exactly by its due time
+        let mut builder = DataBuilder::new(10);
+        builder.depot(node(0.0, 1000, 5));
+        builder.request(node(10.0, 15, 0), node(10.0, 1000, 0));
+        let data = builder.build().unwrap();
+
+        let mut sol = Sol::new(&data);
+        sol.add_route(&vec![0, 1, 2, 0]);
+        assert_eq!(sol.latest_feasible_departure[1], 15 * MULT);
+        assert!(sol.validate().is_empty());
+    }
+
+    #[test]
+    fn ejecting_never_empties_a_route() {
+        let node = |dem| Node {
+            dem,
+            due: 1000,
+            ..Default::default()
+        };
+
+        // either request fills the vehicle
+        let mut builder = DataBuilder::new(10);
+        builder.depot(node(0));
+        let (a, a_delivery) = builder.request(node(10), node(-10));
+        let (b, _) = builder.request(node(10), node(-10));
+        let data = builder.build().unwrap();
+
+        let mut sol = Sol::new(&data);
+        sol.add_route(&vec![0, a, a_delivery, 0]);
+        sol.heap.removed_times[b] = 1;
+
+        let mut ev = Evaluator::new(&data);
+        ev.reset(b);
+        assert!(ev.check_add_to_route_with_k_removed(&sol, a, 1).is_none());
+    }
+}
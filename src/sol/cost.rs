@@ -0,0 +1,186 @@
+use std::fmt;
+
+use itertools::Itertools;
+
+use crate::data::MULT;
+use crate::eval::Eval;
+
+use super::Sol;
+
+/// Objective values of a solution, in instance units.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct Cost {
+    pub distance: f64,
+    pub duration: f64,
+    pub waiting: f64,
+    pub routes: Vec<RouteCost>,
+}
+
+/// Objective values of a single route. A route leaves the depot as late as it can without being
+/// late anywhere, as `Eval` has it, its duration is measured from then until it is back.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct RouteCost {
+    pub route: Vec<usize>,
+    pub distance: f64,
+    pub duration: f64,
+    pub waiting: f64,
+    /// load after leaving each customer
+    pub loads: Vec<i32>,
+    /// start of service at each customer, followed by the arrival back at the depot
+    pub times: Vec<f64>,
+}
+
+fn descale(x: u64) -> f64 {
+    x as f64 / MULT as f64
+}
+
+impl<'a> Sol<'a> {
+    pub fn cost(&self) -> Cost {
+        let mut totals = [0; 2];
+        let routes = self
+            .rs()
+            .into_iter()
+            .sorted()
+            .map(|route| {
+                let (cost, [duration, waiting]) = self.route_cost(route);
+                totals[0] += duration;
+                totals[1] += waiting;
+                cost
+            })
+            .collect::<Vec<_>>();
+
+        // summed before scaling down, so that the totals are not off by rounding errors
+        Cost {
+            distance: descale(self.distance()),
+            duration: descale(totals[0]),
+            waiting: descale(totals[1]),
+            routes,
+        }
+    }
+
+    fn route_cost(&self, route: Vec<usize>) -> (RouteCost, [u64; 2]) {
+        let nodes = || route.iter().chain(std::iter::once(&0));
+
+        let mut e = Eval::new();
+        for &node in nodes() {
+            e.next(node, self.data);
+        }
+        let duration = e.duration();
+
+        // replayed from the departure, so that the times only show the waiting that is left
+        let mut e = Eval {
+            time: e.departure,
+            departure: e.departure,
+            ..Eval::new()
+        };
+        let mut waiting = 0;
+        let mut loads = Vec::with_capacity(route.len());
+        let mut times = Vec::with_capacity(route.len() + 1);
+
+        for &node in nodes() {
+            let arrival = e.time + self.data.time_between(e.node, node);
+            e.next(node, self.data);
+            waiting += e.time - arrival;
+
+            if node != 0 {
+                loads.push(e.capacity);
+            }
+            times.push(descale(e.time));
+        }
+
+        let cost = RouteCost {
+            distance: descale(e.distance),
+            duration: descale(duration),
+            waiting: descale(waiting),
+            loads,
+            times,
+            route,
+        };
+
+        (cost, [duration, waiting])
+    }
+}
+
+impl fmt::Display for Cost {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "routes: {}, distance: {}, duration: {}, waiting: {}",
+            self.routes.len(),
+            self.distance,
+            self.duration,
+            self.waiting
+        )
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::data::builder::{DataBuilder, Node};
+
+    use super::*;
+
+    #[test]
+    fn cost() {
+        let node = |x, dem, start| Node {
+            x,
+            dem,
+            start,
+            due: 100,
+            service: 1,
+            ..Default::default()
+        };
+
+        let mut builder = DataBuilder::new(10);
+        builder.depot(Node {
+            due: 100,
+            ..Default::default()
+        });
+        builder.request(node(10.0, 5, 20), node(20.0, -5, 0));
+        builder.request(node(-5.0, 3, 0), node(-10.0, -3, 40));
+        builder.request(
+            Node {
+                due: 5,
+                ..node(5.0, 2, 0)
+            },
+            node(5.0, -2, 30),
+        );
+        let data = builder.build().unwrap();
+
+        let mut sol = Sol::new(&data);
+        sol.add_route(&vec![0, 1, 2, 0]);
+        sol.add_route(&vec![0, 3, 4, 0]);
+        sol.add_route(&vec![0, 5, 6, 0]);
+
+        let cost = sol.cost();
+
+        // leaves at 10 to be at 1 at 20, 2 at 31, back at 52
+        let first = &cost.routes[0];
+        assert_eq!(first.route, vec![1, 2]);
+        assert_eq!(first.distance, 40.0);
+        assert_eq!(first.duration, 42.0);
+        assert_eq!(first.waiting, 0.0);
+        assert_eq!(first.loads, vec![5, 0]);
+        assert_eq!(first.times, vec![20.0, 31.0, 52.0]);
+
+        // leaves at 29 instead of waiting at 4, is at 3 at 34, at 4 at 40, back at 51
+        let second = &cost.routes[1];
+        assert_eq!(second.route, vec![3, 4]);
+        assert_eq!(second.distance, 20.0);
+        assert_eq!(second.duration, 22.0);
+        assert_eq!(second.waiting, 0.0);
+        assert_eq!(second.loads, vec![3, 0]);
+        assert_eq!(second.times, vec![34.0, 40.0, 51.0]);
+
+        // cannot leave later than 0 to be at 5 by 5, so waits at 6 until 30, back at 36
+        let third = &cost.routes[2];
+        assert_eq!(third.duration, 36.0);
+        assert_eq!(third.waiting, 24.0);
+        assert_eq!(third.times, vec![5.0, 30.0, 36.0]);
+
+        assert_eq!(cost.distance, 70.0);
+        assert_eq!(cost.duration, 100.0);
+        assert_eq!(cost.waiting, 24.0);
+        assert_eq!(cost.distance, sol.distance() as f64 / MULT as f64);
+    }
+}
@@ -1,18 +1,19 @@
 use itertools::Itertools;
 
-use crate::{data::PTS, UNSERVED};
+use crate::UNSERVED;
 
+#[derive(Clone)]
 pub struct Heap {
-    pub removed_times: [u64; PTS],
-    pub removed_idx: [usize; PTS],
+    pub removed_times: Vec<u64>,
+    pub removed_idx: Vec<usize>,
     pub size: usize,
 }
 
 impl Heap {
-    pub fn new() -> Self {
+    pub fn new(points: usize) -> Self {
         Self {
-            removed_times: [0; PTS],
-            removed_idx: [UNSERVED; PTS],
+            removed_times: vec![0; points],
+            removed_idx: vec![UNSERVED; points],
             size: 0,
         }
     }
@@ -60,6 +61,9 @@ impl Heap {
     }
 
     pub fn push(&mut self, idx: usize) {
+        if self.size == self.removed_idx.len() {
+            self.removed_idx.push(UNSERVED);
+        }
         self.removed_idx[self.size] = idx;
         self.size += 1;
     }
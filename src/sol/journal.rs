@@ -0,0 +1,210 @@
+//! Undo log of the changes to a solution, to try a move and take it back without a snapshot.
+
+use std::mem::replace;
+
+use super::Sol;
+
+#[derive(Clone, Copy, Debug)]
+pub(super) enum Change {
+    Next(usize, usize),
+    Prev(usize, usize),
+    First(usize, usize),
+    LatestFeasibleDeparture(usize, u64),
+    Return(usize, u64, u64),
+    RouteInserted(usize),
+    RouteForgotten(usize),
+    RemovedTimes(usize, u64),
+}
+
+/// The previous values of everything changed since `Sol::begin`, oldest first.
+#[derive(Clone, Default, Debug)]
+pub(super) struct Journal {
+    changes: Vec<Change>,
+    /// the ejected pickups in heap order before the heap first changed
+    heap: Option<Vec<usize>>,
+}
+
+impl<'a> Sol<'a> {
+    /// Starts recording changes, so that `rollback` can revert them. Only the random number
+    /// generator is not recorded.
+    pub fn begin(&mut self) {
+        debug_assert!(
+            self.journal.is_none(),
+            "nested transactions are not supported"
+        );
+        self.journal = Some(Journal::default());
+    }
+
+    /// Keeps the changes made since `begin` and stops recording.
+    pub fn commit(&mut self) {
+        debug_assert!(self.journal.is_some());
+        self.journal = None;
+    }
+
+    /// Reverts the changes made since `begin` and stops recording.
+    pub fn rollback(&mut self) {
+        let journal = self.journal.take().expect("rollback without begin");
+
+        for &change in journal.changes.iter().rev() {
+            match change {
+                Change::Next(node, next) => self.next[node] = next,
+                Change::Prev(node, prev) => self.prev[node] = prev,
+                Change::First(node, first) => self.first[node] = first,
+                Change::LatestFeasibleDeparture(node, time) => {
+                    self.latest_feasible_departure[node] = time
+                }
+                Change::Return(node, remaining_time, earliest_return) => {
+                    self.remaining_time[node] = remaining_time;
+                    self.earliest_return[node] = earliest_return;
+                }
+                Change::RouteInserted(first) => {
+                    self.routes.remove(&first);
+                }
+                Change::RouteForgotten(first) => {
+                    self.routes.insert(first);
+                }
+                Change::RemovedTimes(pickup, times) => self.heap.removed_times[pickup] = times,
+            }
+        }
+
+        if let Some(ejected) = journal.heap {
+            self.heap.removed_idx[..ejected.len()].copy_from_slice(&ejected);
+            self.heap.size = ejected.len();
+        }
+    }
+
+    pub(super) fn record(&mut self, change: Change) {
+        if let Some(journal) = self.journal.as_mut() {
+            journal.changes.push(change);
+        }
+    }
+
+    pub(super) fn set_next(&mut self, node: usize, next: usize) {
+        let old = replace(&mut self.next[node], next);
+        self.record(Change::Next(node, old));
+    }
+
+    pub(super) fn set_prev(&mut self, node: usize, prev: usize) {
+        let old = replace(&mut self.prev[node], prev);
+        self.record(Change::Prev(node, old));
+    }
+
+    pub(super) fn set_first(&mut self, node: usize, first: usize) {
+        let old = replace(&mut self.first[node], first);
+        self.record(Change::First(node, old));
+    }
+
+    pub(super) fn set_latest_feasible_departure(&mut self, node: usize, time: u64) {
+        let old = replace(&mut self.latest_feasible_departure[node], time);
+        self.record(Change::LatestFeasibleDeparture(node, old));
+    }
+
+    pub(super) fn set_return(&mut self, node: usize, remaining_time: u64, earliest_return: u64) {
+        let old_remaining_time = replace(&mut self.remaining_time[node], remaining_time);
+        let old_earliest_return = replace(&mut self.earliest_return[node], earliest_return);
+        self.record(Change::Return(
+            node,
+            old_remaining_time,
+            old_earliest_return,
+        ));
+    }
+
+    pub(super) fn set_removed_times(&mut self, pickup: usize, times: u64) {
+        let old = replace(&mut self.heap.removed_times[pickup], times);
+        self.record(Change::RemovedTimes(pickup, old));
+    }
+
+    pub(super) fn insert_route(&mut self, first: usize) {
+        if self.routes.insert(first) {
+            self.record(Change::RouteInserted(first));
+        }
+    }
+
+    pub(super) fn forget_route(&mut self, first: usize) {
+        if self.routes.remove(&first) {
+            self.record(Change::RouteForgotten(first));
+        }
+    }
+
+    /// Keeps the order of the heap before its first change, the heap reorders too much to
+    /// record single changes.
+    pub(super) fn save_heap(&mut self) {
+        if let Some(journal) = self.journal.as_mut() {
+            if journal.heap.is_none() {
+                journal.heap = Some(self.heap.removed_idx[..self.heap.size].to_vec());
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::evaluator::Evaluator;
+    use crate::tests::{instance, short_conf};
+    use crate::{Conf, Ges, LogFormat, Prng};
+    use rand::SeedableRng;
+
+    #[test]
+    fn rollback_reverts_moves_and_swaps() {
+        let data = instance(20);
+        let mut ev = Evaluator::new(&data);
+        *ev.rng() = Prng::seed_from_u64(2);
+
+        // routes of several pairs, so that there are pairs to move around
+        let mut sol = Sol::new(&data);
+        sol.initialize();
+        let conf = Conf {
+            seed: Some(1),
+            log_format: LogFormat::Silent,
+            ..short_conf(5)
+        };
+        Ges::new(&data).ges(&mut sol, conf);
+        assert_eq!(sol.routes_number(), 5);
+
+        let first = sol.random_route_first();
+        sol.remove_route(first);
+
+        let before = sol.clone();
+        let same = |sol: &Sol| {
+            assert_eq!(sol.snapshot(), before.snapshot());
+            assert_eq!(sol.next, before.next);
+            assert_eq!(sol.prev, before.prev);
+            assert_eq!(sol.first, before.first);
+            assert_eq!(
+                sol.latest_feasible_departure,
+                before.latest_feasible_departure
+            );
+        };
+
+        // an insertion ejecting other pairs into the heap
+        sol.begin();
+        sol.pop();
+        let mov = sol.try_insert_k(first, &mut ev).unwrap();
+        sol.make_move(&mov);
+        assert!(sol.heap.size > 0);
+        sol.inc();
+        assert_ne!(sol.snapshot(), before.snapshot());
+        sol.rollback();
+        same(&sol);
+
+        sol.begin();
+        for _ in 0..50 {
+            sol.perturb(&mut ev);
+        }
+        let route = sol.random_route_first();
+        sol.remove_route(route);
+        sol.rollback();
+        same(&sol);
+        assert!(sol.check_routes());
+
+        sol.begin();
+        sol.pop();
+        let mov = sol.try_insert(first, &mut ev).unwrap();
+        sol.make_move(&mov);
+        sol.commit();
+        let committed = sol.snapshot();
+        assert_ne!(committed, before.snapshot());
+        assert!(!committed.ejected.contains(&first));
+    }
+}
@@ -0,0 +1,320 @@
+//! Solutions in the format used by the SINTEF Li & Lim benchmark pages.
+
+use std::fmt;
+use std::io::{self, Write};
+use std::path::Path;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use itertools::Itertools;
+
+use super::validate::{check_routes, Violation};
+use super::Sol;
+
+#[derive(Debug, Default, Clone)]
+pub struct SolutionHeader {
+    pub instance: String,
+    pub authors: String,
+    pub reference: String,
+    pub date: String,
+}
+
+impl<'a> Sol<'a> {
+    pub fn write_solution<W: Write>(&self, w: &mut W, header: &SolutionHeader) -> io::Result<()> {
+        field(w, "Instance name", &header.instance)?;
+        field(w, "Authors", &header.authors)?;
+        field(w, "Reference", &header.reference)?;
+        field(w, "Date", &header.date)?;
+        writeln!(w, "Solution :")?;
+
+        for (i, r) in self.rs().iter().sorted().enumerate() {
+            write!(w, "Route {} :", i + 1)?;
+            for x in r {
+                write!(w, " {x}")?;
+            }
+            writeln!(w)?;
+        }
+
+        Ok(())
+    }
+}
+
+#[derive(Debug)]
+pub enum SolutionError {
+    Io(io::Error),
+    Parse { line: usize, msg: String },
+    UnknownNode { route: usize, node: usize },
+    Duplicate(usize),
+    Unserved(usize),
+    PairSplit { pickup: usize, delivery: usize },
+    DeliveryBeforePickup { pickup: usize, delivery: usize },
+    Infeasible { route: usize, node: usize },
+    TooLong { route: usize },
+}
+
+impl fmt::Display for SolutionError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SolutionError::Io(e) => write!(f, "cannot read solution: {e}"),
+            SolutionError::Parse { line, msg } => write!(f, "line {line}: {msg}"),
+            SolutionError::UnknownNode { route, node } => {
+                write!(f, "route {route}: there is no customer {node}")
+            }
+            SolutionError::Duplicate(node) => write!(f, "customer {node} is served twice"),
+            SolutionError::Unserved(node) => write!(f, "customer {node} is not served"),
+            SolutionError::PairSplit { pickup, delivery } => {
+                write!(
+                    f,
+                    "pickup {pickup} and delivery {delivery} are in different routes"
+                )
+            }
+            SolutionError::DeliveryBeforePickup { pickup, delivery } => {
+                write!(f, "delivery {delivery} is visited before pickup {pickup}")
+            }
+            SolutionError::Infeasible { route, node } => {
+                write!(
+                    f,
+                    "route {route}: capacity or time window violated at {node}"
+                )
+            }
+            SolutionError::TooLong { route } => {
+                write!(f, "route {route} takes longer than the maximum duration")
+            }
+        }
+    }
+}
+
+impl std::error::Error for SolutionError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            SolutionError::Io(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<Violation> for SolutionError {
+    fn from(v: Violation) -> Self {
+        match v {
+            Violation::UnknownNode { route, node } => SolutionError::UnknownNode { route, node },
+            Violation::Duplicate(node) => SolutionError::Duplicate(node),
+            Violation::Unserved(node) => SolutionError::Unserved(node),
+            Violation::PairSplit { pickup, delivery } => {
+                SolutionError::PairSplit { pickup, delivery }
+            }
+            Violation::DeliveryBeforePickup { pickup, delivery } => {
+                SolutionError::DeliveryBeforePickup { pickup, delivery }
+            }
+            Violation::Capacity { route, node, .. } | Violation::TimeWindow { route, node, .. } => {
+                SolutionError::Infeasible { route, node }
+            }
+            Violation::Duration { route, .. } => SolutionError::TooLong { route },
+        }
+    }
+}
+
+impl From<io::Error> for SolutionError {
+    fn from(e: io::Error) -> Self {
+        SolutionError::Io(e)
+    }
+}
+
+pub fn read_routes(path: &Path) -> Result<Vec<Vec<usize>>, SolutionError> {
+    parse_routes(&std::fs::read_to_string(path)?)
+}
+
+/// Routes of a solution, skipping the header and empty routes.
+pub fn parse_routes(contents: &str) -> Result<Vec<Vec<usize>>, SolutionError> {
+    let mut routes = vec![];
+
+    for (i, l) in contents.lines().enumerate() {
+        let Some(rest) = l.trim().strip_prefix("Route") else {
+            continue;
+        };
+
+        let Some((_, nodes)) = rest.split_once(':') else {
+            return Err(SolutionError::Parse {
+                line: i + 1,
+                msg: "expected 'Route <number> : <customers>'".to_string(),
+            });
+        };
+
+        let route = nodes
+            .split_whitespace()
+            .map(|x| x.parse::<usize>())
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|e| SolutionError::Parse {
+                line: i + 1,
+                msg: e.to_string(),
+            })?;
+
+        if !route.is_empty() {
+            routes.push(route);
+        }
+    }
+
+    Ok(routes)
+}
+
+impl<'a> Sol<'a> {
+    /// Starts from the given routes (without the depot) instead of `initialize`, provided they
+    /// serve every customer exactly once and are feasible.
+    pub fn initialize_from(&mut self, routes: &[Vec<usize>]) -> Result<(), SolutionError> {
+        if let Some(violation) = check_routes(self.data, routes).into_iter().next() {
+            return Err(violation.into());
+        }
+
+        for route in routes {
+            let mut with_depot = Vec::with_capacity(route.len() + 2);
+            with_depot.push(0);
+            with_depot.extend(route);
+            with_depot.push(0);
+            self.add_route(&with_depot);
+        }
+
+        Ok(())
+    }
+}
+
+fn field<W: Write>(w: &mut W, name: &str, value: &str) -> io::Result<()> {
+    if value.is_empty() {
+        writeln!(w, "{name}:")
+    } else {
+        writeln!(w, "{name}: {value}")
+    }
+}
+
+/// Current UTC date as `YYYY-MM-DD`.
+pub fn today() -> String {
+    let secs = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0);
+
+    date_from_days((secs / 86400) as i64)
+}
+
+// days since 1970-01-01 to a civil date, after http://howardhinnant.github.io/date_algorithms.html
+fn date_from_days(days: i64) -> String {
+    let z = days + 719468;
+    let era = z.div_euclid(146097);
+    let doe = z.rem_euclid(146097);
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + i64::from(month <= 2);
+
+    format!("{year:04}-{month:02}-{day:02}")
+}
+
+#[cfg(test)]
+mod test {
+    use crate::tests::requests;
+
+    use super::*;
+
+    #[test]
+    fn dates() {
+        assert_eq!(date_from_days(0), "1970-01-01");
+        assert_eq!(date_from_days(59), "1970-03-01");
+        assert_eq!(date_from_days(11016), "2000-02-29");
+        assert_eq!(date_from_days(19722), "2023-12-31");
+    }
+
+    #[test]
+    fn read() {
+        let contents = "Instance name: lc101\n\
+                        Authors:\n\
+                        Solution :\n\
+                        Route 1 : 1 2\n\
+                        Route 2 :\n\
+                        Route 3 : 3 5 4 6\n";
+
+        let routes = parse_routes(contents).unwrap();
+        assert_eq!(routes, vec![vec![1, 2], vec![3, 5, 4, 6]]);
+
+        let data = requests(3);
+        let mut sol = Sol::new(&data);
+        sol.initialize_from(&routes).unwrap();
+        assert_eq!(sol.routes_number(), 2);
+        assert!(sol.check_routes());
+
+        assert!(matches!(
+            parse_routes("Route 1 : 1 x"),
+            Err(SolutionError::Parse { line: 1, .. })
+        ));
+        assert!(matches!(
+            parse_routes("Route 1 1 2"),
+            Err(SolutionError::Parse { line: 1, .. })
+        ));
+    }
+
+    #[test]
+    fn invalid() {
+        let data = requests(2);
+        let check = |routes: Vec<Vec<usize>>| Sol::new(&data).initialize_from(&routes);
+
+        assert!(matches!(
+            check(vec![vec![1, 2, 3, 4, 5]]),
+            Err(SolutionError::UnknownNode { route: 1, node: 5 })
+        ));
+        assert!(matches!(
+            check(vec![vec![1, 2], vec![3, 4, 1]]),
+            Err(SolutionError::Duplicate(1))
+        ));
+        assert!(matches!(
+            check(vec![vec![1, 2]]),
+            Err(SolutionError::Unserved(3))
+        ));
+        assert!(matches!(
+            check(vec![vec![1, 3], vec![2, 4]]),
+            Err(SolutionError::PairSplit {
+                pickup: 1,
+                delivery: 2
+            })
+        ));
+        assert!(matches!(
+            check(vec![vec![2, 1], vec![3, 4]]),
+            Err(SolutionError::DeliveryBeforePickup {
+                pickup: 1,
+                delivery: 2
+            })
+        ));
+        // every pickup loads 10 of 50, so the sixth one in a row exceeds the capacity
+        let data = requests(6);
+        assert!(matches!(
+            Sol::new(&data)
+                .initialize_from(&[(1..=12).step_by(2).chain((2..=12).step_by(2)).collect()]),
+            Err(SolutionError::Infeasible { route: 1, node: 11 })
+        ));
+    }
+
+    #[test]
+    fn write() {
+        let data = requests(2);
+        let mut sol = Sol::new(&data);
+        sol.add_route(&vec![0, 3, 4, 0]);
+        sol.add_route(&vec![0, 1, 2, 0]);
+
+        let header = SolutionHeader {
+            instance: "lc101".to_string(),
+            authors: "A. Author".to_string(),
+            ..Default::default()
+        };
+
+        let mut out = vec![];
+        sol.write_solution(&mut out, &header).unwrap();
+
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            "Instance name: lc101\n\
+             Authors: A. Author\n\
+             Reference:\n\
+             Date:\n\
+             Solution :\n\
+             Route 1 : 1 2\n\
+             Route 2 : 3 4\n"
+        );
+    }
+}
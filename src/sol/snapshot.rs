@@ -0,0 +1,253 @@
+//! Compact copies of the state of a solution, independent of the instance they belong to.
+
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::io;
+use std::path::Path;
+
+use crate::UNSERVED;
+
+use super::validate::{check_routes, Violation};
+use super::Sol;
+
+/// The routes of a solution together with its ejected pairs. Restoring it gives back the same
+/// solution, apart from the random number generator.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SolSnapshot {
+    /// routes without the depot, ordered by their first customers
+    pub routes: Vec<Vec<usize>>,
+    /// ejected pickups from the bottom of the heap to its top
+    pub ejected: Vec<usize>,
+    /// how many times every pickup was ejected, by node
+    pub removed_times: Vec<u64>,
+}
+
+#[derive(Debug)]
+pub enum SnapshotError {
+    Io(io::Error),
+    Format(serde_json::Error),
+    Points { expected: usize, found: usize },
+    Ejected(usize),
+    Invalid(Violation),
+}
+
+impl fmt::Display for SnapshotError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SnapshotError::Io(e) => write!(f, "cannot access snapshot: {e}"),
+            SnapshotError::Format(e) => write!(f, "invalid snapshot: {e}"),
+            SnapshotError::Points { expected, found } => {
+                write!(f, "snapshot of {found} points, the instance has {expected}")
+            }
+            SnapshotError::Ejected(node) => write!(f, "node {node} cannot be ejected"),
+            SnapshotError::Invalid(v) => write!(f, "{v}"),
+        }
+    }
+}
+
+impl std::error::Error for SnapshotError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            SnapshotError::Io(e) => Some(e),
+            SnapshotError::Format(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for SnapshotError {
+    fn from(e: io::Error) -> Self {
+        SnapshotError::Io(e)
+    }
+}
+
+impl From<serde_json::Error> for SnapshotError {
+    fn from(e: serde_json::Error) -> Self {
+        SnapshotError::Format(e)
+    }
+}
+
+impl SolSnapshot {
+    pub fn read(path: &Path) -> Result<Self, SnapshotError> {
+        let file = io::BufReader::new(std::fs::File::open(path)?);
+        Ok(serde_json::from_reader(file)?)
+    }
+
+    pub fn write(&self, path: &Path) -> Result<(), SnapshotError> {
+        let mut file = io::BufWriter::new(std::fs::File::create(path)?);
+        serde_json::to_writer(&mut file, self)?;
+        io::Write::flush(&mut file)?;
+        Ok(())
+    }
+}
+
+impl<'a> Sol<'a> {
+    pub fn snapshot(&self) -> SolSnapshot {
+        SolSnapshot {
+            routes: self.rs(),
+            ejected: self.heap.removed_idx[..self.heap.size].to_vec(),
+            removed_times: self.heap.removed_times.clone(),
+        }
+    }
+
+    /// Replaces the routes and ejected pairs with those of the snapshot, which must be feasible
+    /// and serve every pair not ejected. On error the solution is left unchanged.
+    pub fn restore(&mut self, snapshot: &SolSnapshot) -> Result<(), SnapshotError> {
+        self.check_snapshot(snapshot)?;
+        self.restore_unchecked(snapshot);
+
+        Ok(())
+    }
+
+    /// `restore` without checking the snapshot, for those taken of a solution of the same data.
+    pub(crate) fn restore_unchecked(&mut self, snapshot: &SolSnapshot) {
+        debug_assert!(self.check_snapshot(snapshot).is_ok());
+
+        self.next.fill(UNSERVED);
+        self.prev.fill(UNSERVED);
+        self.first.fill(UNSERVED);
+        self.latest_feasible_departure.fill(0);
+        self.latest_feasible_departure[0] = self.data.pts[0].due;
+        self.remaining_time.fill(0);
+        self.earliest_return.fill(0);
+        self.earliest_return[0] = self.data.pts[0].start;
+        self.routes.clear();
+
+        for route in snapshot.routes.iter() {
+            let with_depot: Vec<usize> = [0].iter().chain(route).chain(&[0]).copied().collect();
+            self.add_route(&with_depot);
+        }
+
+        self.heap.removed_times.clone_from(&snapshot.removed_times);
+        self.heap.size = 0;
+        for &pickup in snapshot.ejected.iter() {
+            self.heap.push(pickup);
+        }
+    }
+
+    /// Whether `restore` accepts the snapshot.
+    pub fn check_snapshot(&self, snapshot: &SolSnapshot) -> Result<(), SnapshotError> {
+        let data = self.data;
+        if snapshot.removed_times.len() != data.points {
+            return Err(SnapshotError::Points {
+                expected: data.points,
+                found: snapshot.removed_times.len(),
+            });
+        }
+
+        let mut ejected = vec![false; data.points];
+        for &pickup in snapshot.ejected.iter() {
+            if pickup == 0 || pickup >= data.points || data.pts[pickup].is_delivery {
+                return Err(SnapshotError::Ejected(pickup));
+            }
+            if std::mem::replace(&mut ejected[pickup], true) {
+                return Err(SnapshotError::Ejected(pickup));
+            }
+            ejected[data.pair_of(pickup)] = true;
+        }
+
+        for route in snapshot.routes.iter() {
+            if let Some(&node) = route.iter().find(|&&n| n < data.points && ejected[n]) {
+                let pickup = if data.pts[node].is_delivery {
+                    data.pair_of(node)
+                } else {
+                    node
+                };
+                return Err(SnapshotError::Ejected(pickup));
+            }
+        }
+
+        match check_routes(data, &snapshot.routes)
+            .into_iter()
+            .find(|v| !matches!(*v, Violation::Unserved(node) if ejected[node]))
+        {
+            Some(violation) => Err(SnapshotError::Invalid(violation)),
+            None => Ok(()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::evaluator::Evaluator;
+    use crate::tests::{instance, requests};
+    use crate::Prng;
+    use rand::SeedableRng;
+
+    #[test]
+    fn restores_routes_and_ejected_pairs() {
+        let data = instance(10);
+        let mut ev = Evaluator::new(&data);
+        *ev.rng() = Prng::seed_from_u64(1);
+
+        let mut sol = Sol::new(&data);
+        sol.rng = Prng::seed_from_u64(1);
+        sol.initialize();
+        sol.remove_route(3);
+        while let Some(top) = sol.top() {
+            sol.pop();
+            let mov = sol.try_insert_1(top, &mut ev).unwrap();
+            sol.make_move(&mov);
+        }
+        let first = sol.random_route_first();
+        sol.remove_route(first);
+
+        let snapshot = sol.snapshot();
+        assert_eq!(snapshot.routes.len(), 8);
+        assert_eq!(snapshot.ejected, [first]);
+
+        let mut restored = Sol::new(&data);
+        restored.restore(&snapshot).unwrap();
+        assert_eq!(restored.snapshot(), snapshot);
+        for &node in snapshot.routes.iter().flatten() {
+            assert_eq!(
+                restored.latest_feasible_departure[node],
+                sol.latest_feasible_departure[node]
+            );
+        }
+        assert_eq!(restored.top(), Some(first));
+        assert_eq!(restored.removed_times(first), 1);
+        assert!(restored.check_routes());
+
+        let json = serde_json::to_string(&snapshot).unwrap();
+        assert_eq!(
+            serde_json::from_str::<SolSnapshot>(&json).unwrap(),
+            snapshot
+        );
+    }
+
+    #[test]
+    fn rejects_foreign_snapshots() {
+        let data = requests(3);
+        let mut sol = Sol::new(&data);
+        sol.initialize();
+        let before = sol.snapshot();
+
+        let mut snapshot = before.clone();
+        snapshot.removed_times.pop();
+        assert!(matches!(
+            sol.restore(&snapshot),
+            Err(SnapshotError::Points {
+                expected: 7,
+                found: 6
+            })
+        ));
+
+        let mut snapshot = before.clone();
+        snapshot.ejected.push(1);
+        assert!(matches!(
+            sol.restore(&snapshot),
+            Err(SnapshotError::Ejected(1))
+        ));
+
+        let mut snapshot = before.clone();
+        snapshot.routes.pop();
+        assert!(matches!(
+            sol.restore(&snapshot),
+            Err(SnapshotError::Invalid(Violation::Unserved(5)))
+        ));
+
+        assert_eq!(sol.snapshot(), before);
+    }
+}
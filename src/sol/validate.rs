@@ -0,0 +1,253 @@
+//! Checks of complete solutions, independent of the linked lists kept by `Sol`.
+
+use std::fmt;
+
+use crate::data::{Data, MULT};
+use crate::eval::Eval;
+
+use super::Sol;
+
+/// Something wrong with a solution. Routes are numbered from 1, node 0 stands for the return to
+/// the depot, and times are in instance units.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Violation {
+    UnknownNode {
+        route: usize,
+        node: usize,
+    },
+    Duplicate(usize),
+    Unserved(usize),
+    PairSplit {
+        pickup: usize,
+        delivery: usize,
+    },
+    DeliveryBeforePickup {
+        pickup: usize,
+        delivery: usize,
+    },
+    Capacity {
+        route: usize,
+        node: usize,
+        load: i32,
+    },
+    TimeWindow {
+        route: usize,
+        node: usize,
+        arrival: f64,
+        due: f64,
+    },
+    Duration {
+        route: usize,
+        duration: f64,
+        max: f64,
+    },
+}
+
+impl fmt::Display for Violation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Violation::UnknownNode { route, node } => {
+                write!(f, "route {route}: there is no customer {node}")
+            }
+            Violation::Duplicate(node) => write!(f, "customer {node} is served more than once"),
+            Violation::Unserved(node) => write!(f, "customer {node} is not served"),
+            Violation::PairSplit { pickup, delivery } => {
+                write!(
+                    f,
+                    "pickup {pickup} and delivery {delivery} are in different routes"
+                )
+            }
+            Violation::DeliveryBeforePickup { pickup, delivery } => {
+                write!(f, "delivery {delivery} is visited before pickup {pickup}")
+            }
+            Violation::Capacity { route, node, load } => {
+                write!(
+                    f,
+                    "route {route}: load {load} exceeds the capacity after {node}"
+                )
+            }
+            Violation::TimeWindow {
+                route,
+                node,
+                arrival,
+                due,
+            } => write!(
+                f,
+                "route {route}: arrives at {node} at {arrival}, after its due time {due}"
+            ),
+            Violation::Duration {
+                route,
+                duration,
+                max,
+            } => write!(
+                f,
+                "route {route}: takes {duration}, longer than the maximum duration {max}"
+            ),
+        }
+    }
+}
+
+/// Every violation in routes given without the depot: customers served other than exactly
+/// once, pairs not served pickup first in one route, and exceeded capacities, time windows and
+/// route durations.
+pub fn check_routes(data: &Data, routes: &[Vec<usize>]) -> Vec<Violation> {
+    let mut violations = vec![];
+    let mut position = vec![None; data.points];
+
+    for (r, route) in routes.iter().enumerate() {
+        for (i, &node) in route.iter().enumerate() {
+            if node == 0 || node >= data.points {
+                violations.push(Violation::UnknownNode { route: r + 1, node });
+            } else if position[node].is_some() {
+                violations.push(Violation::Duplicate(node));
+            } else {
+                position[node] = Some((r, i));
+            }
+        }
+    }
+
+    for node in 1..data.points {
+        let Some((route, i)) = position[node] else {
+            violations.push(Violation::Unserved(node));
+            continue;
+        };
+
+        let pt = &data.pts[node];
+        if !pt.is_delivery {
+            let (pickup, delivery) = (node, pt.pair);
+            match position[delivery] {
+                Some((r, _)) if r != route => {
+                    violations.push(Violation::PairSplit { pickup, delivery })
+                }
+                Some((_, j)) if j < i => {
+                    violations.push(Violation::DeliveryBeforePickup { pickup, delivery })
+                }
+                _ => {}
+            }
+        }
+    }
+
+    let descale = |t: u64| t as f64 / MULT as f64;
+    for (r, route) in routes.iter().enumerate() {
+        let mut e = Eval::new();
+        let known = route
+            .iter()
+            .filter(|&&node| node != 0 && node < data.points);
+        for &node in known.chain(std::iter::once(&0)) {
+            e.next(node, data);
+
+            if e.capacity > data.max_cap {
+                violations.push(Violation::Capacity {
+                    route: r + 1,
+                    node,
+                    load: e.capacity,
+                });
+            }
+            if e.arrives_too_late(data) {
+                violations.push(Violation::TimeWindow {
+                    route: r + 1,
+                    node,
+                    arrival: descale(e.time),
+                    due: descale(data.pts[node].due),
+                });
+            }
+        }
+
+        if e.takes_too_long(data) {
+            violations.push(Violation::Duration {
+                route: r + 1,
+                duration: descale(e.duration()),
+                max: descale(data.max_duration),
+            });
+        }
+    }
+
+    violations
+}
+
+impl<'a> Sol<'a> {
+    /// Every violation in the current routes; ejected pairs count as unserved.
+    pub fn validate(&self) -> Vec<Violation> {
+        check_routes(self.data, &self.rs())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::data::builder::{DataBuilder, Node};
+    use crate::tests::requests;
+
+    #[test]
+    fn reports_every_violation() {
+        let data = requests(3);
+
+        let mut sol = Sol::new(&data);
+        sol.initialize();
+        assert!(sol.validate().is_empty());
+
+        let violations = check_routes(&data, &[vec![2, 1, 7], vec![3, 4, 3]]);
+        assert_eq!(
+            violations[..5],
+            [
+                Violation::UnknownNode { route: 1, node: 7 },
+                Violation::Duplicate(3),
+                Violation::DeliveryBeforePickup {
+                    pickup: 1,
+                    delivery: 2
+                },
+                Violation::Unserved(5),
+                Violation::Unserved(6),
+            ]
+        );
+        assert_eq!(violations.len(), 5);
+
+        // every pickup loads 10 of 50
+        let data = requests(6);
+        let route: Vec<usize> = (1..=12).step_by(2).chain((2..=12).step_by(2)).collect();
+        assert_eq!(
+            check_routes(&data, &[route]),
+            [Violation::Capacity {
+                route: 1,
+                node: 11,
+                load: 60
+            }]
+        );
+    }
+
+    #[test]
+    fn time_windows() {
+        let node = |x, dem, due| Node {
+            x,
+            dem,
+            due,
+            ..Default::default()
+        };
+
+        let mut builder = DataBuilder::new(10);
+        builder.depot(node(0.0, 0, 100));
+        builder.request(node(10.0, 5, 5), node(10.0, -5, 100));
+        let data = builder.build().unwrap();
+
+        assert_eq!(
+            check_routes(&data, &[vec![1, 2]]),
+            [Violation::TimeWindow {
+                route: 1,
+                node: 1,
+                arrival: 10.0,
+                due: 5.0
+            }]
+        );
+
+        // unknown nodes are skipped when following the route
+        assert_eq!(
+            check_routes(&data, &[vec![1, 0, 2]])[1..],
+            [Violation::TimeWindow {
+                route: 1,
+                node: 1,
+                arrival: 10.0,
+                due: 5.0
+            }]
+        );
+    }
+}
@@ -1,5 +1,7 @@
+use crate::data::MULT;
 use crate::sol::Sol;
-use std::time::Instant;
+use serde::{Deserialize, Serialize};
+use std::time::{Duration, Instant};
 
 pub struct HeapSize {
     min: usize,
@@ -18,12 +20,22 @@ impl HeapSize {
         self.min = self.min.min(heap_size);
         self.max = self.max.max(heap_size);
     }
+
+    pub fn min(&self) -> usize {
+        self.min
+    }
+
+    pub fn max(&self) -> usize {
+        self.max
+    }
 }
 
 #[derive(Default)]
 pub struct Iterations {
     total: usize,
     current: usize,
+    /// value of `current` when the heap last got smaller than ever before in this route removal
+    last_progress: usize,
 }
 
 impl Iterations {
@@ -35,6 +47,15 @@ impl Iterations {
     pub fn current(&self) -> usize {
         self.current
     }
+
+    pub fn total(&self) -> usize {
+        self.total
+    }
+
+    /// Iterations since the heap last got smaller than ever before in this route removal.
+    pub fn since_progress(&self) -> usize {
+        self.current - self.last_progress
+    }
 }
 
 pub struct Time {
@@ -52,6 +73,18 @@ impl Time {
     }
 }
 
+/// Everything `Stats` counts, with times in seconds, to continue counting in another run.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Counters {
+    pub total: usize,
+    pub current: usize,
+    pub last_progress: usize,
+    pub heap_min: usize,
+    pub heap_max: usize,
+    pub time: f64,
+    pub route_time: f64,
+}
+
 pub struct Stats {
     time: Time,
     iterations: Iterations,
@@ -70,10 +103,14 @@ impl Stats {
     pub fn reset(&mut self) {
         self.heap_size = HeapSize::new();
         self.iterations.current = 0;
+        self.iterations.last_progress = 0;
         self.time.route_start = Instant::now();
     }
 
     pub fn add_iteration(&mut self, heap_size: usize) {
+        if heap_size < self.heap_size.min {
+            self.iterations.last_progress = self.iterations.current;
+        }
         self.heap_size.change(heap_size);
         self.iterations.inc();
     }
@@ -107,26 +144,72 @@ impl Stats {
     }
 
     pub fn print_after_route_removal(&self, solution: &Sol) {
-        let routes = solution.routes_number();
-        let rs = if solution.heap.size > 0 {
-            1
-        } else {
-            0
-        };
         println!(
             "routes: {} after {}, {:?} (total {:?})",
-            routes + rs,
+            solution.routes_number(),
             self.iterations().current(),
             self.time.route_start.elapsed(),
             self.time.start.elapsed(),
         );
     }
 
+    pub fn print_after_distance_minimization(&self, solution: &Sol) {
+        println!(
+            "distance: {} after {:?} (total {:?})",
+            solution.distance() as f64 / MULT as f64,
+            self.time.route_start.elapsed(),
+            self.time.start.elapsed(),
+        );
+    }
+
     pub fn iterations(&self) -> &Iterations {
         &self.iterations
     }
 
+    pub fn heap_size(&self) -> &HeapSize {
+        &self.heap_size
+    }
+
     pub fn total_time(&self) -> std::time::Duration {
         self.time.start.elapsed()
     }
+
+    pub fn route_time(&self) -> std::time::Duration {
+        self.time.route_start.elapsed()
+    }
+
+    pub fn counters(&self) -> Counters {
+        Counters {
+            total: self.iterations.total,
+            current: self.iterations.current,
+            last_progress: self.iterations.last_progress,
+            heap_min: self.heap_size.min,
+            heap_max: self.heap_size.max,
+            time: self.total_time().as_secs_f64(),
+            route_time: self.route_time().as_secs_f64(),
+        }
+    }
+
+    /// Continues counting from the counters, as if the time they measured has just passed.
+    pub fn resume(&mut self, counters: &Counters) {
+        let now = Instant::now();
+        let before = |secs: f64| {
+            Duration::try_from_secs_f64(secs)
+                .ok()
+                .and_then(|elapsed| now.checked_sub(elapsed))
+                .unwrap_or(now)
+        };
+
+        self.time.start = before(counters.time);
+        self.time.route_start = before(counters.route_time);
+        self.iterations = Iterations {
+            total: counters.total,
+            current: counters.current,
+            last_progress: counters.last_progress,
+        };
+        self.heap_size = HeapSize {
+            min: counters.heap_min,
+            max: counters.heap_max,
+        };
+    }
 }